@@ -0,0 +1,74 @@
+//! Arrays, which hold values of one type in any number of dimensions
+
+use crate::error::ErrorKind;
+
+/// Elements in each dimension of an array used before it is dimensioned, for subscripts 0 to 10
+pub const DEFAULT_SIZE: usize = 11;
+
+/// Bytes free for variables between a program and DOS, which an array must fit in
+const FREE_BYTES: usize = 36_351;
+
+/// The most dimensions an array can have, as Applesoft keeps the count in a byte
+const MAX_DIMENSIONS: usize = 255;
+
+/// A type of value that can be stored in an array
+pub trait Element: Clone + Default {
+    /// Bytes each element takes up in Applesoft's memory
+    const BYTES: usize;
+}
+
+impl Element for f64 {
+    const BYTES: usize = 5;
+}
+
+impl Element for i16 {
+    const BYTES: usize = 2;
+}
+
+impl Element for String {
+    // the characters are kept elsewhere, behind a length and a pointer
+    const BYTES: usize = 3;
+}
+
+/// An array, with its elements in one block
+#[derive(Debug, Clone)]
+pub struct Array<T> {
+    /// The number of elements in each dimension
+    sizes: Vec<usize>,
+    elements: Vec<T>,
+}
+
+impl<T: Element> Array<T> {
+    /// Make an array with the given number of elements in each dimension, all 0 or empty; an
+    /// array too big for Applesoft's memory raises `?OUT OF MEMORY ERROR`
+    pub fn new(sizes: Vec<usize>) -> Result<Self, ErrorKind> {
+        if sizes.len() > MAX_DIMENSIONS {
+            return Err(ErrorKind::OutOfMemory);
+        }
+        let count = sizes
+            .iter()
+            .try_fold(1_usize, |count, &size| count.checked_mul(size))
+            .filter(|&count| count.saturating_mul(T::BYTES) <= FREE_BYTES)
+            .ok_or(ErrorKind::OutOfMemory)?;
+        Ok(Self {
+            elements: vec![T::default(); count],
+            sizes,
+        })
+    }
+
+    /// Get the element at the given subscripts, which must be one for each dimension and in
+    /// range
+    pub fn element(&mut self, subscripts: &[usize]) -> Result<&mut T, ErrorKind> {
+        if subscripts.len() != self.sizes.len() {
+            return Err(ErrorKind::BadSubscript);
+        }
+        let mut offset = 0;
+        for (&subscript, &size) in subscripts.iter().zip(&self.sizes) {
+            if subscript >= size {
+                return Err(ErrorKind::BadSubscript);
+            }
+            offset = offset * size + subscript;
+        }
+        Ok(&mut self.elements[offset])
+    }
+}
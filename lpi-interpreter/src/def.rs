@@ -0,0 +1,53 @@
+//! DEF FN statement logic, and calls of the functions it defines
+
+use crate::{
+    error::ErrorKind,
+    interpreter::{Interpreter, UserFunction},
+    value::Value,
+};
+use lpi_lexer::span::Spanned;
+use lpi_parser::ast::{Expr, Variable};
+
+#[allow(clippy::module_name_repetitions)]
+pub fn def_statement(
+    interpreter: &mut Interpreter,
+    name: &Variable,
+    parameter: &Variable,
+    body: &Spanned<Expr>,
+) -> Result<(), ErrorKind> {
+    // the body is kept in the program, so a function cannot be defined in immediate mode
+    if interpreter.is_direct() {
+        return Err(ErrorKind::IllegalDirect);
+    }
+    let name = interpreter.function_name(name);
+    interpreter.set_function(
+        &name,
+        UserFunction {
+            parameter: parameter.clone(),
+            body: body.clone(),
+        },
+    );
+    Ok(())
+}
+
+/// Call a function defined with `DEF FN`; the parameter holds the argument only while the body
+/// is evaluated, after which the variable of the same name gets its value back
+pub fn call_user_function(
+    interpreter: &mut Interpreter,
+    name: &Variable,
+    argument: &Spanned<Expr>,
+) -> Result<Value, ErrorKind> {
+    let name = interpreter.function_name(name);
+    let function = interpreter
+        .get_function(&name)
+        .ok_or(ErrorKind::UndefinedFunction)?;
+    let argument = interpreter.evaluate_expression(argument)?;
+    let parameter = interpreter.variable_name(&function.parameter);
+    let global = interpreter.get_float_scalar(&parameter);
+    interpreter.enter_function()?;
+    interpreter.set_float_scalar(&parameter, argument);
+    let value = interpreter.evaluate_expression(&function.body);
+    interpreter.set_float_scalar(&parameter, global);
+    interpreter.leave_function();
+    Ok(Value::Number(value?))
+}
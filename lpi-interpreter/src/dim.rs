@@ -0,0 +1,12 @@
+//! DIM statement logic
+
+use crate::{error::ErrorKind, interpreter::Interpreter};
+use lpi_parser::ast::Variable;
+
+#[allow(clippy::module_name_repetitions)]
+pub fn dim_statement(interpreter: &mut Interpreter, arrays: &[Variable]) -> Result<(), ErrorKind> {
+    for array in arrays {
+        interpreter.dim_array(array)?;
+    }
+    Ok(())
+}
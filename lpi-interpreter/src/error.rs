@@ -0,0 +1,105 @@
+//! Errors raised while running a program
+
+use lpi_lexer::span::Span;
+
+/// The kinds of error Applesoft reports, plus failures writing the output
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug)]
+pub enum ErrorKind {
+    /// `?SYNTAX ERROR`
+    Syntax,
+    /// `?TYPE MISMATCH ERROR`
+    TypeMismatch,
+    /// `?OVERFLOW ERROR`
+    Overflow,
+    /// `?DIVISION BY ZERO ERROR`
+    DivisionByZero,
+    /// `?ILLEGAL QUANTITY ERROR`
+    IllegalQuantity,
+    /// `?BAD SUBSCRIPT ERROR`
+    BadSubscript,
+    /// `?REDIM'D ARRAY ERROR`
+    RedimdArray,
+    /// `?UNDEF'D STATEMENT ERROR`
+    UndefinedStatement,
+    /// `?RETURN WITHOUT GOSUB ERROR`
+    ReturnWithoutGosub,
+    /// `?OUT OF MEMORY ERROR`
+    OutOfMemory,
+    /// `?NEXT WITHOUT FOR ERROR`
+    NextWithoutFor,
+    /// `?OUT OF DATA ERROR`
+    OutOfData,
+    /// `?UNDEF'D FUNCTION ERROR`
+    UndefinedFunction,
+    /// `?ILLEGAL DIRECT ERROR`
+    IllegalDirect,
+    /// `?STRING TOO LONG ERROR`
+    StringTooLong,
+    /// The output could not be written
+    Io(std::io::Error),
+}
+
+impl ErrorKind {
+    /// Get the message Applesoft prints between `?` and `ERROR`
+    const fn message(&self) -> &'static str {
+        match self {
+            Self::Syntax => "SYNTAX",
+            Self::TypeMismatch => "TYPE MISMATCH",
+            Self::Overflow => "OVERFLOW",
+            Self::DivisionByZero => "DIVISION BY ZERO",
+            Self::IllegalQuantity => "ILLEGAL QUANTITY",
+            Self::BadSubscript => "BAD SUBSCRIPT",
+            Self::RedimdArray => "REDIM'D ARRAY",
+            Self::UndefinedStatement => "UNDEF'D STATEMENT",
+            Self::ReturnWithoutGosub => "RETURN WITHOUT GOSUB",
+            Self::OutOfMemory => "OUT OF MEMORY",
+            Self::NextWithoutFor => "NEXT WITHOUT FOR",
+            Self::OutOfData => "OUT OF DATA",
+            Self::UndefinedFunction => "UNDEF'D FUNCTION",
+            Self::IllegalDirect => "ILLEGAL DIRECT",
+            Self::StringTooLong => "STRING TOO LONG",
+            Self::Io(_) => "I/O",
+        }
+    }
+}
+
+impl From<std::io::Error> for ErrorKind {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// An error that stopped the program, with the number of the line that raised it
+#[derive(Debug)]
+pub struct Error {
+    /// What went wrong
+    pub kind: ErrorKind,
+    /// The line that was running, or `None` for a statement run in immediate mode
+    pub line: Option<u16>,
+    /// Where the operand that raised the error is in the source, or its statement if no one
+    /// operand raised it
+    pub span: Span,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::Io(error) => write!(fmt, "{error}"),
+            kind => write!(fmt, "?{} ERROR", kind.message()),
+        }?;
+        if let Some(line) = self.line {
+            write!(fmt, " IN {line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! FOR and NEXT statement logic
+
+use crate::{
+    error::ErrorKind,
+    interpreter::{ForFrame, Frame, Interpreter},
+};
+use lpi_lexer::span::Spanned;
+use lpi_parser::ast::{Expr, Variable};
+
+#[allow(clippy::module_name_repetitions)]
+pub fn for_statement(
+    interpreter: &mut Interpreter,
+    variable: &Variable,
+    start: &Spanned<Expr>,
+    end: &Spanned<Expr>,
+    step: Option<&Spanned<Expr>>,
+) -> Result<(), ErrorKind> {
+    let name = interpreter.variable_name(variable);
+    let start = interpreter.evaluate_expression(start)?;
+    interpreter.set_float_scalar(&name, start);
+    let limit = interpreter.evaluate_expression(end)?;
+    let step = match step {
+        Some(step) => interpreter.evaluate_expression(step)?,
+        None => 1.0,
+    };
+
+    // a loop that reuses the variable of an open loop replaces it, along with every loop
+    // opened inside it
+    if let Some(index) = interpreter.find_for(Some(&name)) {
+        interpreter.truncate_stack(index);
+    }
+    // the body always runs once, as the limit is only checked by NEXT
+    interpreter.push_frame(Frame::For(ForFrame {
+        variable: name,
+        limit,
+        step,
+        body: interpreter.counter(),
+    }))
+}
+
+/// Step each loop in turn, stopping at the first that goes round again
+pub fn next_statement(
+    interpreter: &mut Interpreter,
+    variables: &[Variable],
+) -> Result<(), ErrorKind> {
+    if variables.is_empty() {
+        let _looped = next_loop(interpreter, None)?;
+        return Ok(());
+    }
+    for variable in variables {
+        let name = interpreter.variable_name(variable);
+        if next_loop(interpreter, Some(&name))? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Step the loop for the variable, or the innermost loop; returns whether it goes round again
+fn next_loop(interpreter: &mut Interpreter, variable: Option<&str>) -> Result<bool, ErrorKind> {
+    let index = interpreter
+        .find_for(variable)
+        .ok_or(ErrorKind::NextWithoutFor)?;
+    // the loops inside this one are finished with
+    interpreter.truncate_stack(index + 1);
+    // Safety: find_for only finds FOR frames
+    #[allow(clippy::unwrap_used)]
+    let frame = interpreter.for_frame(index).unwrap().clone();
+
+    let value = interpreter.get_float_scalar(&frame.variable) + frame.step;
+    interpreter.set_float_scalar(&frame.variable, value);
+    // the loop is done once the variable has passed the limit in the direction of the step,
+    // or has reached the limit when the step is 0
+    if sign(value - frame.limit) == sign(frame.step) {
+        interpreter.truncate_stack(index);
+        Ok(false)
+    } else {
+        interpreter.jump(frame.body);
+        Ok(true)
+    }
+}
+
+/// Get -1, 0 or 1 for a negative, zero or positive number
+fn sign(number: f64) -> i8 {
+    if number > 0.0 {
+        1
+    } else if number < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
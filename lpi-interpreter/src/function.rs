@@ -0,0 +1,109 @@
+//! Built-in function logic
+
+use crate::{
+    error::ErrorKind,
+    interpreter::Interpreter,
+    value::{checked_number, format_number, read_number, Value, MAX_STRING_LENGTH},
+};
+use lpi_lexer::span::Spanned;
+use lpi_parser::ast::{Expr, Function};
+
+/// Evaluate the arguments of a built-in function and call it
+pub fn call_function(
+    interpreter: &mut Interpreter,
+    function: Function,
+    arguments: &[Spanned<Expr>],
+) -> Result<Value, ErrorKind> {
+    let value = match (function, arguments) {
+        (Function::Rnd, [argument]) => {
+            let argument = interpreter.evaluate_expression(argument)?;
+            Value::Number(interpreter.rnd(argument))
+        }
+        (Function::Len, [string]) => {
+            let length = interpreter.evaluate_string(string)?.chars().count();
+            // strings are at most 255 characters
+            #[allow(clippy::cast_precision_loss)]
+            Value::Number(length as f64)
+        }
+        (Function::Left, [string, length]) => {
+            let string = interpreter.evaluate_string(string)?;
+            let length = usize::from(interpreter.evaluate_byte(length)?);
+            Value::String(string.chars().take(length).collect())
+        }
+        (Function::Right, [string, length]) => {
+            let string = interpreter.evaluate_string(string)?;
+            let length = usize::from(interpreter.evaluate_byte(length)?);
+            let start = string.chars().count().saturating_sub(length);
+            Value::String(string.chars().skip(start).collect())
+        }
+        (Function::Mid, [string, start, length @ ..]) => {
+            let string = interpreter.evaluate_string(string)?;
+            // the start counts from 1, so 0 is not allowed
+            let start = match interpreter.evaluate_byte(start)? {
+                0 => return Err(ErrorKind::IllegalQuantity),
+                start => usize::from(start),
+            };
+            let length = match length {
+                [length] => usize::from(interpreter.evaluate_byte(length)?),
+                _ => MAX_STRING_LENGTH,
+            };
+            Value::String(string.chars().skip(start - 1).take(length).collect())
+        }
+        (Function::Str, [number]) => {
+            Value::String(format_number(interpreter.evaluate_expression(number)?))
+        }
+        (Function::Val, [string]) => {
+            let (number, _rest) = read_number(&interpreter.evaluate_string(string)?);
+            Value::Number(checked_number(number)?)
+        }
+        (Function::Chr, [code]) => {
+            Value::String(char::from(interpreter.evaluate_byte(code)?).to_string())
+        }
+        (Function::Asc, [string]) => {
+            let string = interpreter.evaluate_string(string)?;
+            let code = string.chars().next().ok_or(ErrorKind::IllegalQuantity)?;
+            Value::Number(u32::from(code).into())
+        }
+        (
+            Function::Abs
+            | Function::Sgn
+            | Function::Int
+            | Function::Sqr
+            | Function::Exp
+            | Function::Log
+            | Function::Sin
+            | Function::Cos
+            | Function::Tan
+            | Function::Atn,
+            [argument],
+        ) => {
+            let argument = interpreter.evaluate_expression(argument)?;
+            let number = numeric_function(function, argument)?;
+            Value::Number(checked_number(number)?)
+        }
+        // the parser checks the number of arguments, but an expression built by hand may not
+        _ => return Err(ErrorKind::Syntax),
+    };
+    Ok(value)
+}
+
+/// Apply a function of a number
+fn numeric_function(function: Function, number: f64) -> Result<f64, ErrorKind> {
+    let result = match function {
+        Function::Abs => number.abs(),
+        Function::Sgn if number == 0.0 => 0.0,
+        Function::Sgn => number.signum(),
+        Function::Int => number.floor(),
+        Function::Sqr if number < 0.0 => return Err(ErrorKind::IllegalQuantity),
+        Function::Sqr => number.sqrt(),
+        Function::Exp => number.exp(),
+        Function::Log if number <= 0.0 => return Err(ErrorKind::IllegalQuantity),
+        Function::Log => number.ln(),
+        Function::Sin => number.sin(),
+        Function::Cos => number.cos(),
+        Function::Tan => number.tan(),
+        Function::Atn => number.atan(),
+        _ => unreachable!("only called for functions of a number"),
+    };
+    Ok(result)
+}
@@ -0,0 +1,39 @@
+//! GOSUB, ON ... GOSUB, RETURN and POP statement logic
+
+use crate::{
+    error::ErrorKind,
+    goto::select_target,
+    interpreter::{Frame, Interpreter},
+};
+use lpi_lexer::span::Spanned;
+use lpi_parser::ast::Expr;
+
+#[allow(clippy::module_name_repetitions)]
+pub fn gosub_statement(interpreter: &mut Interpreter, target: u16) -> Result<(), ErrorKind> {
+    // the program counter is already past the GOSUB, which is where RETURN goes back to
+    interpreter.push_frame(Frame::Gosub(interpreter.counter()))?;
+    interpreter.goto(target)
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub fn on_gosub_statement(
+    interpreter: &mut Interpreter,
+    index: &Spanned<Expr>,
+    targets: &[u16],
+) -> Result<(), ErrorKind> {
+    match select_target(interpreter, index, targets)? {
+        Some(target) => gosub_statement(interpreter, target),
+        None => Ok(()),
+    }
+}
+
+pub fn return_statement(interpreter: &mut Interpreter) -> Result<(), ErrorKind> {
+    let counter = interpreter.pop_gosub()?;
+    interpreter.jump(counter);
+    Ok(())
+}
+
+pub fn pop_statement(interpreter: &mut Interpreter) -> Result<(), ErrorKind> {
+    let _unused = interpreter.pop_gosub()?;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! GOTO and ON ... GOTO statement logic
+
+use crate::{error::ErrorKind, interpreter::Interpreter};
+use lpi_lexer::span::Spanned;
+use lpi_parser::ast::Expr;
+
+#[allow(clippy::module_name_repetitions)]
+pub fn goto_statement(interpreter: &mut Interpreter, target: u16) -> Result<(), ErrorKind> {
+    interpreter.goto(target)
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub fn on_goto_statement(
+    interpreter: &mut Interpreter,
+    index: &Spanned<Expr>,
+    targets: &[u16],
+) -> Result<(), ErrorKind> {
+    match select_target(interpreter, index, targets)? {
+        Some(target) => interpreter.goto(target),
+        None => Ok(()),
+    }
+}
+
+/// Pick the line that `ON index` chooses, counting from 1; an index of 0 or past the end of the
+/// list chooses nothing, so execution falls through to the next statement
+pub fn select_target(
+    interpreter: &mut Interpreter,
+    index: &Spanned<Expr>,
+    targets: &[u16],
+) -> Result<Option<u16>, ErrorKind> {
+    let index = usize::from(interpreter.evaluate_byte(index)?);
+    Ok(index
+        .checked_sub(1)
+        .and_then(|index| targets.get(index))
+        .copied())
+}
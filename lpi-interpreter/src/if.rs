@@ -0,0 +1,18 @@
+//! IF statement logic
+
+use crate::{error::ErrorKind, interpreter::Interpreter};
+use lpi_lexer::span::Spanned;
+use lpi_parser::ast::Expr;
+
+#[allow(clippy::module_name_repetitions)]
+pub fn if_statement(
+    interpreter: &mut Interpreter,
+    condition: &Spanned<Expr>,
+) -> Result<(), ErrorKind> {
+    // a true condition carries on with the statement after THEN, while a false one skips
+    // everything else on the line, including statements after later colons
+    if interpreter.evaluate_expression(condition)? == 0.0 {
+        interpreter.skip_line();
+    }
+    Ok(())
+}
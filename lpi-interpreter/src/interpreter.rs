@@ -1,25 +1,440 @@
 //! An interpreter for an Applesoft II BASIC program
 
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    io::Write,
+    rc::Rc,
+};
 
-use lpi_parser::{node_type::NodeType, parse_node::ParseNode};
+use crate::{
+    array::{Array, Element, DEFAULT_SIZE},
+    def::call_user_function,
+    error::{Error, ErrorKind},
+    function::call_function,
+    random::Random,
+    value::{checked_number, Value, MAX_STRING_LENGTH},
+};
+use lpi_lexer::{
+    span::{Span, Spanned},
+    token::IdentifierKind,
+};
+use lpi_parser::ast::{BinaryOp, Expr, Line, Program, Statement, UnaryOp, Variable};
 
-#[allow(dead_code)]
+/// Where execution has got to: the line being run and the next statement on it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ProgramCounter {
+    /// Index of the line in the ordered line index, or `None` for the direct line that is run
+    /// in immediate mode
+    pub line: Option<usize>,
+    /// Index of the next statement to run on the line
+    pub statement: usize,
+}
+
+/// An open FOR loop
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForFrame {
+    /// The name of the loop variable
+    pub variable: String,
+    /// The value the loop runs up to, or down to for a negative step
+    pub limit: f64,
+    /// The amount added to the loop variable on each `NEXT`
+    pub step: f64,
+    /// Where the body of the loop starts, just after the FOR statement
+    pub body: ProgramCounter,
+}
+
+/// Where READ has got to in the DATA statements of the program
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct DataPointer {
+    /// Index of the line in the ordered line index
+    pub line: usize,
+    /// Index of the statement on the line
+    pub statement: usize,
+    /// Index of the next item to read, if the statement is a DATA statement
+    pub item: usize,
+}
+
+/// A function defined with `DEF FN`
+#[derive(Debug, PartialEq, Clone)]
+pub struct UserFunction {
+    /// The variable that holds the argument while the body is evaluated
+    pub parameter: Variable,
+    /// The expression the function gives
+    pub body: Spanned<Expr>,
+}
+
+/// An entry on the stack that Applesoft keeps its GOSUB returns and FOR loops on
+#[derive(Debug, PartialEq, Clone)]
+pub enum Frame {
+    /// Where a `RETURN` goes back to
+    Gosub(ProgramCounter),
+    /// An open FOR loop
+    For(ForFrame),
+}
+
+impl Frame {
+    /// Get the number of bytes the frame takes on the 6502 stack. A GOSUB keeps the 2 byte
+    /// return address of the statement dispatcher under its 5 byte frame, and is rounded up to
+    /// 8 so that the stack holds 24 nested GOSUBs as in the ROM
+    const fn size(&self) -> usize {
+        match self {
+            Self::Gosub(_) => 8,
+            Self::For(_) => 18,
+        }
+    }
+}
+
+/// Bytes of the 6502 stack that Applesoft leaves free for frames; a frame that does not fit
+/// raises `?OUT OF MEMORY ERROR`, which stops runaway recursion. It holds 24 nested GOSUBs or
+/// 10 nested FOR loops
+const STACK_BYTES: usize = 192;
+
+/// How many calls of functions defined with `DEF FN` may be nested; a call past the limit
+/// raises `?OUT OF MEMORY ERROR`, as the ROM does when a function keeps calling itself
+const MAX_FUNCTION_DEPTH: usize = 32;
+
+/// Columns on the Apple II text screen; printing past the last one wraps to the next line
+const SCREEN_WIDTH: usize = 40;
+
+/// Runs a program, keeping its lines, its variables and where execution has got to
 pub struct Interpreter {
-    root: ParseNode,
+    /// The numbered lines of the program, in line number order
+    lines: Vec<Rc<Line>>,
+    /// The lines without a number, in source order
+    immediate: Vec<Rc<Line>>,
+    /// The line without a number being run in immediate mode, if any
+    direct: Option<Rc<Line>>,
+    /// Whether a line without a number has gone into the program, so it need not be run again
+    entered_program: bool,
+    counter: ProgramCounter,
+    stack: Vec<Frame>,
+    data: DataPointer,
+    /// The line to report an error against instead of the line being run, if any
+    error_line: Option<u16>,
+    /// The source text to report an error against instead of the statement being run, if any
+    error_span: Option<Span>,
+    /// The column the cursor is in, counting from 0
+    column: usize,
+    random: Random,
     float_scalars: HashMap<String, f64>,
-    float_arrays: HashMap<String, Vec<f64>>,
+    float_arrays: HashMap<String, Array<f64>>,
+    integer_scalars: HashMap<String, i16>,
+    integer_arrays: HashMap<String, Array<i16>>,
+    string_scalars: HashMap<String, String>,
+    string_arrays: HashMap<String, Array<String>>,
+    functions: HashMap<String, Rc<UserFunction>>,
+    /// How many calls of functions defined with `DEF FN` are being evaluated
+    function_depth: usize,
+    /// Whether to warn about names that Applesoft would confuse
+    strict: bool,
+    /// The first spelling seen of each variable, by the name it is stored under
+    spellings: HashMap<String, String>,
+    warnings: Vec<String>,
 }
 
 impl Interpreter {
-    pub fn new(root: ParseNode) -> Self {
+    /// Store the lines of the program; as when typing a program in, a numbered line replaces
+    /// an earlier line with the same number
+    #[must_use]
+    pub fn new(program: &Program) -> Self {
+        let mut lines = BTreeMap::new();
+        let mut immediate = Vec::new();
+        for line in &program.lines {
+            match line.number {
+                Some(number) => {
+                    let _unused = lines.insert(number, Rc::new(line.clone()));
+                }
+                None => immediate.push(Rc::new(line.clone())),
+            }
+        }
         Self {
-            root,
+            lines: lines.into_values().collect(),
+            immediate,
+            direct: None,
+            entered_program: false,
+            counter: ProgramCounter {
+                line: Some(0),
+                statement: 0,
+            },
+            stack: Vec::new(),
+            data: DataPointer::default(),
+            error_line: None,
+            error_span: None,
+            column: 0,
+            random: Random::unseeded(),
             float_scalars: HashMap::new(),
             float_arrays: HashMap::new(),
+            integer_scalars: HashMap::new(),
+            integer_arrays: HashMap::new(),
+            string_scalars: HashMap::new(),
+            string_arrays: HashMap::new(),
+            functions: HashMap::new(),
+            function_depth: 0,
+            strict: false,
+            spellings: HashMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Warn about variables that are spelled differently but are the same to Applesoft, such
+    /// as `SCORE` and `SC`
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Seed the random numbers that `RND` gives, so that a program gives the same output on
+    /// every run
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random = Random::new(seed);
+    }
+
+    /// Get the warnings raised in strict mode, in the order they were found
+    #[must_use]
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Interpret the program.
+    ///
+    /// Lines without a number are run first in immediate mode, in source order, as if they were
+    /// typed in once the numbered lines had been entered. The numbered lines are then run from
+    /// the first one, unless a line without a number has already gone into them.
+    /// # Errors
+    /// Returns the Applesoft error that stopped the program, such as `?SYNTAX ERROR IN 10` when
+    /// it runs a line holding a syntax error, or a failure writing to the output
+    pub fn interpret<T: Write>(&mut self, output: &mut T) -> Result<(), Error> {
+        for line in self.immediate.clone() {
+            self.run_direct(line);
+            crate::run(self, output)?;
+        }
+        if self.entered_program {
+            return Ok(());
+        }
+        self.run_program();
+        crate::run(self, output)
+    }
+
+    /// Get the index of the line with the given number
+    pub fn line_index(&self, number: u16) -> Option<usize> {
+        self.lines
+            .binary_search_by_key(&Some(number), |line| line.number)
+            .ok()
+    }
+
+    /// Continue execution from the start of the line with the given number
+    pub fn goto(&mut self, number: u16) -> Result<(), ErrorKind> {
+        let line = self
+            .line_index(number)
+            .ok_or(ErrorKind::UndefinedStatement)?;
+        self.entered_program = true;
+        self.jump(ProgramCounter {
+            line: Some(line),
+            statement: 0,
+        });
+        Ok(())
+    }
+
+    pub const fn counter(&self) -> ProgramCounter {
+        self.counter
+    }
+
+    /// Push a frame onto the stack, if there is room for it
+    pub fn push_frame(&mut self, frame: Frame) -> Result<(), ErrorKind> {
+        let used: usize = self.stack.iter().map(Frame::size).sum();
+        if used + frame.size() > STACK_BYTES {
+            return Err(ErrorKind::OutOfMemory);
+        }
+        self.stack.push(frame);
+        Ok(())
+    }
+
+    /// Pop the newest GOSUB frame and get where it returns to; the loops opened since that
+    /// GOSUB are popped along with it
+    pub fn pop_gosub(&mut self) -> Result<ProgramCounter, ErrorKind> {
+        while let Some(frame) = self.stack.pop() {
+            if let Frame::Gosub(counter) = frame {
+                return Ok(counter);
+            }
+        }
+        Err(ErrorKind::ReturnWithoutGosub)
+    }
+
+    /// Find the newest FOR frame for the loop variable, or the newest FOR frame of all when no
+    /// variable is given. The search stops at the newest GOSUB frame, so a subroutine cannot
+    /// reach the loops of its caller.
+    pub fn find_for(&self, variable: Option<&str>) -> Option<usize> {
+        for (index, frame) in self.stack.iter().enumerate().rev() {
+            match frame {
+                Frame::Gosub(_) => return None,
+                Frame::For(frame) if variable.is_none_or(|name| name == frame.variable) => {
+                    return Some(index);
+                }
+                Frame::For(_) => {}
+            }
+        }
+        None
+    }
+
+    /// Get the FOR frame at the given index of the stack
+    pub fn for_frame(&self, index: usize) -> Option<&ForFrame> {
+        match self.stack.get(index)? {
+            Frame::For(frame) => Some(frame),
+            Frame::Gosub(_) => None,
+        }
+    }
+
+    /// Discard the frames from the given index of the stack upwards
+    pub fn truncate_stack(&mut self, len: usize) {
+        self.stack.truncate(len);
+    }
+
+    /// Continue execution from the given position
+    pub const fn jump(&mut self, counter: ProgramCounter) {
+        self.counter = counter;
+    }
+
+    /// Check whether the statement being run is on the direct line, in immediate mode
+    pub const fn is_direct(&self) -> bool {
+        self.counter.line.is_none()
+    }
+
+    /// Run the stored program from its first line
+    pub fn run_program(&mut self) {
+        self.jump(ProgramCounter {
+            line: Some(0),
+            statement: 0,
+        });
+    }
+
+    /// Run a line without a number in immediate mode
+    pub fn run_direct(&mut self, line: Rc<Line>) {
+        self.direct = Some(line);
+        self.jump(ProgramCounter {
+            line: None,
+            statement: 0,
+        });
+    }
+
+    /// Skip the rest of the line being run; immediate mode stops, as the direct line is its
+    /// only line
+    pub fn skip_line(&mut self) {
+        match self.counter.line {
+            Some(index) => self.jump(ProgramCounter {
+                line: Some(index + 1),
+                statement: 0,
+            }),
+            None => self.direct = None,
         }
     }
 
+    /// Stop execution, as if the end of the program had been reached
+    pub fn end(&mut self) {
+        self.jump(ProgramCounter {
+            line: Some(self.lines.len()),
+            statement: 0,
+        });
+    }
+
+    /// Get the next DATA item to read, with the number of the line it is on and the span of its
+    /// DATA statement
+    pub fn next_data_item(&mut self) -> Result<(String, u16, Span), ErrorKind> {
+        loop {
+            let line = self.lines.get(self.data.line).ok_or(ErrorKind::OutOfData)?;
+            match line.statements.get(self.data.statement) {
+                None => {
+                    self.data = DataPointer {
+                        line: self.data.line + 1,
+                        ..DataPointer::default()
+                    };
+                }
+                Some(Spanned {
+                    value: Statement::Data(items),
+                    span,
+                }) if self.data.item < items.len() => {
+                    self.data.item += 1;
+                    // Safety: only numbered lines are in the ordered line index
+                    #[allow(clippy::unwrap_used)]
+                    let number = line.number.unwrap();
+                    return Ok((items[self.data.item - 1].clone(), number, *span));
+                }
+                Some(_) => {
+                    self.data.statement += 1;
+                    self.data.item = 0;
+                }
+            }
+        }
+    }
+
+    /// Read DATA items from the start of the program again
+    pub fn restore(&mut self) {
+        self.data = DataPointer::default();
+    }
+
+    /// Report the error being raised against the given line and statement instead of the
+    /// statement being run, as the ROM does for a bad DATA item
+    pub fn report_error_in(&mut self, line: u16, span: Span) {
+        self.error_line = Some(line);
+        self.error_span = Some(span);
+    }
+
+    /// Blame the error in a result on the given source text, unless it was already blamed on
+    /// text inside it, so that an error points at the innermost operand that raised it
+    fn locate<T>(&mut self, result: Result<T, ErrorKind>, span: Span) -> Result<T, ErrorKind> {
+        if result.is_err() {
+            let _unused = self.error_span.get_or_insert(span);
+        }
+        result
+    }
+
+    /// Get the line and the source text to report an error against instead of the statement
+    /// being run, where there are any
+    pub fn take_error_location(&mut self) -> (Option<u16>, Option<Span>) {
+        (self.error_line.take(), self.error_span.take())
+    }
+
+    /// Get the line holding the next statement to run and the index of that statement, and
+    /// move the program counter past it; `None` once execution has stopped. A program runs on
+    /// into the following line, but immediate mode stops at the end of the direct line.
+    pub fn fetch(&mut self) -> Option<(Rc<Line>, usize)> {
+        loop {
+            let line = match self.counter.line {
+                Some(index) => Rc::clone(self.lines.get(index)?),
+                None => Rc::clone(self.direct.as_ref()?),
+            };
+            let statement = self.counter.statement;
+            if statement < line.statements.len() {
+                self.counter.statement += 1;
+                return Some((line, statement));
+            }
+            self.counter = ProgramCounter {
+                line: Some(self.counter.line? + 1),
+                statement: 0,
+            };
+        }
+    }
+
+    /// Get the column the cursor is in, counting from 0
+    pub const fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Move the cursor past text that has been printed
+    pub fn track_output(&mut self, text: &str) {
+        for ch in text.chars() {
+            self.column = if ch == '\n' {
+                0
+            } else {
+                (self.column + 1) % SCREEN_WIDTH
+            };
+        }
+    }
+
+    /// Get a random number for `RND` with the given argument
+    pub fn rnd(&mut self, argument: f64) -> f64 {
+        self.random.rnd(argument)
+    }
+
     pub fn get_float_scalar(&self, name: &str) -> f64 {
         self.float_scalars.get(name).copied().unwrap_or(0.0)
     }
@@ -28,163 +443,509 @@ impl Interpreter {
         let _unused = self.float_scalars.insert(name.to_owned(), value);
     }
 
-    pub fn dim_float_array(&mut self, name: &str, size: usize) {
-        let _unused = self.float_arrays.insert(name.to_owned(), vec![0.0; size]);
+    pub fn get_float_array(&mut self, name: &str, subscripts: &[usize]) -> Result<f64, ErrorKind> {
+        array_element(&mut self.float_arrays, name, subscripts).copied()
+    }
+
+    pub fn set_float_array(
+        &mut self,
+        name: &str,
+        subscripts: &[usize],
+        value: f64,
+    ) -> Result<(), ErrorKind> {
+        *array_element(&mut self.float_arrays, name, subscripts)? = value;
+        Ok(())
+    }
+
+    pub fn get_integer_scalar(&self, name: &str) -> i16 {
+        self.integer_scalars.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn set_integer_scalar(&mut self, name: &str, value: i16) {
+        let _unused = self.integer_scalars.insert(name.to_owned(), value);
     }
 
-    #[allow(dead_code)]
-    pub fn get_float_array(&mut self, name: &str, index: usize) -> Result<f64, String> {
-        if !self.float_arrays.contains_key(name) {
-            self.dim_float_array(name, 10);
+    pub fn get_integer_array(
+        &mut self,
+        name: &str,
+        subscripts: &[usize],
+    ) -> Result<i16, ErrorKind> {
+        array_element(&mut self.integer_arrays, name, subscripts).copied()
+    }
+
+    pub fn set_integer_array(
+        &mut self,
+        name: &str,
+        subscripts: &[usize],
+        value: i16,
+    ) -> Result<(), ErrorKind> {
+        *array_element(&mut self.integer_arrays, name, subscripts)? = value;
+        Ok(())
+    }
+
+    pub fn get_string_scalar(&self, name: &str) -> String {
+        self.string_scalars.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn set_string_scalar(&mut self, name: &str, value: String) {
+        let _unused = self.string_scalars.insert(name.to_owned(), value);
+    }
+
+    pub fn get_string_array(
+        &mut self,
+        name: &str,
+        subscripts: &[usize],
+    ) -> Result<String, ErrorKind> {
+        array_element(&mut self.string_arrays, name, subscripts).cloned()
+    }
+
+    pub fn set_string_array(
+        &mut self,
+        name: &str,
+        subscripts: &[usize],
+        value: String,
+    ) -> Result<(), ErrorKind> {
+        *array_element(&mut self.string_arrays, name, subscripts)? = value;
+        Ok(())
+    }
+
+    /// Get a function defined with `DEF FN`
+    pub fn get_function(&self, name: &str) -> Option<Rc<UserFunction>> {
+        self.functions.get(name).cloned()
+    }
+
+    /// Define a function, replacing any function with the same name
+    pub fn set_function(&mut self, name: &str, function: UserFunction) {
+        let _unused = self.functions.insert(name.to_owned(), Rc::new(function));
+    }
+
+    /// Start a call of a function defined with `DEF FN`, failing if too many calls are nested
+    pub fn enter_function(&mut self) -> Result<(), ErrorKind> {
+        if self.function_depth == MAX_FUNCTION_DEPTH {
+            return Err(ErrorKind::OutOfMemory);
+        }
+        self.function_depth += 1;
+        Ok(())
+    }
+
+    /// Finish a call of a function defined with `DEF FN`
+    pub fn leave_function(&mut self) {
+        self.function_depth -= 1;
+    }
+
+    /// Create an array with the largest subscripts given for each of its dimensions; an array
+    /// can only be dimensioned once, including by using it
+    pub fn dim_array(&mut self, array: &Variable) -> Result<(), ErrorKind> {
+        let name = self.variable_name(array);
+        let sizes = self
+            .evaluate_subscripts(array)?
+            .into_iter()
+            .map(|subscript| subscript + 1)
+            .collect();
+        match array.kind {
+            IdentifierKind::Float => dim_array(&mut self.float_arrays, &name, sizes),
+            IdentifierKind::Integer => dim_array(&mut self.integer_arrays, &name, sizes),
+            IdentifierKind::String => dim_array(&mut self.string_arrays, &name, sizes),
+        }
+    }
+
+    /// Evaluate the subscripts of an array element; a fraction is truncated
+    pub fn evaluate_subscripts(&mut self, variable: &Variable) -> Result<Vec<usize>, ErrorKind> {
+        variable
+            .subscripts
+            .iter()
+            .map(|subscript| {
+                let subscript = to_integer(self.evaluate_expression(subscript)?)?;
+                usize::try_from(subscript).map_err(|_| ErrorKind::IllegalQuantity)
+            })
+            .collect()
+    }
+
+    /// Evaluate an expression which must give a number from 0 to 255, as Applesoft requires of
+    /// byte arguments such as the index of `ON`; a fraction is truncated
+    pub fn evaluate_byte(&mut self, expr: &Spanned<Expr>) -> Result<u8, ErrorKind> {
+        let number = self.evaluate_expression(expr)?.trunc();
+        if !(0.0..256.0).contains(&number) {
+            return self.locate(Err(ErrorKind::IllegalQuantity), expr.span);
+        }
+        // safe because of the check above
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(number as u8)
+    }
+
+    /// Evaluate an expression which must give a number
+    pub fn evaluate_expression(&mut self, expr: &Spanned<Expr>) -> Result<f64, ErrorKind> {
+        let number = match self.evaluate(expr)? {
+            Value::Number(number) => Ok(number),
+            Value::String(_) => Err(ErrorKind::TypeMismatch),
+        };
+        self.locate(number, expr.span)
+    }
+
+    /// Evaluate an expression which must give a string
+    pub fn evaluate_string(&mut self, expr: &Spanned<Expr>) -> Result<String, ErrorKind> {
+        let string = match self.evaluate(expr)? {
+            Value::Number(_) => Err(ErrorKind::TypeMismatch),
+            Value::String(string) => Ok(string),
+        };
+        self.locate(string, expr.span)
+    }
+
+    /// Evaluate an expression
+    pub fn evaluate(&mut self, expr: &Spanned<Expr>) -> Result<Value, ErrorKind> {
+        let value = self.evaluate_node(&expr.value);
+        self.locate(value, expr.span)
+    }
+
+    /// Evaluate the outermost node of an expression
+    fn evaluate_node(&mut self, expr: &Expr) -> Result<Value, ErrorKind> {
+        match expr {
+            Expr::Number(number) => Ok(Value::Number(checked_number(*number)?)),
+            Expr::String(string) => Ok(Value::String(string.clone())),
+            Expr::Variable(variable) => self.evaluate_variable(variable),
+            Expr::Unary(operator, operand) => {
+                let operand = self.evaluate_expression(operand)?;
+                match operator {
+                    UnaryOp::Negate => Ok(Value::Number(-operand)),
+                    UnaryOp::Plus => Ok(Value::Number(operand)),
+                    UnaryOp::Not => Ok(Value::from_bool(operand == 0.0)),
+                }
+            }
+            Expr::Binary(operator, left, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Self::evaluate_binary(*operator, left, right)
+            }
+            Expr::Function(function, arguments) => call_function(self, *function, arguments),
+            Expr::UserFunction(name, argument) => call_user_function(self, name, argument),
+        }
+    }
+
+    /// Get the name a variable is stored under: the first two characters of its name, which
+    /// are all that Applesoft looks at, and its type suffix
+    pub fn variable_name(&mut self, variable: &Variable) -> String {
+        let name = significant_name(variable);
+        if self.strict {
+            // an array is a different variable from the scalar of the same name
+            let array = if variable.subscripts.is_empty() {
+                ""
+            } else {
+                "()"
+            };
+            let spelling = format!("{}{}{array}", variable.name, variable.kind.suffix());
+            self.check_spelling(format!("{name}{array}"), spelling, "variable");
+        }
+        name
+    }
+
+    /// Get the name a function defined with `DEF FN` is stored under, which is shortened like
+    /// the name of a variable
+    pub fn function_name(&mut self, function: &Variable) -> String {
+        let name = significant_name(function);
+        if self.strict {
+            let spelling = format!("FN {}", function.name);
+            self.check_spelling(format!("FN {name}"), spelling, "function");
+        }
+        name
+    }
+
+    /// Warn the first time a name is spelled differently from when it was first seen
+    fn check_spelling(&mut self, key: String, spelling: String, kind: &str) {
+        let line = self
+            .counter
+            .line
+            .and_then(|index| self.lines.get(index))
+            .and_then(|line| line.number);
+        let first = self
+            .spellings
+            .entry(key)
+            .or_insert_with(|| spelling.clone());
+        if *first == spelling {
+            return;
+        }
+        let mut warning = format!("{first} and {spelling} are the same {kind}");
+        if let Some(line) = line {
+            warning.push_str(&format!(" in line {line}"));
         }
-        // Safety: we just created this
-        #[allow(clippy::unwrap_used)]
-        let array = self.float_arrays.get(name).unwrap();
-        if index >= array.len() {
-            return Err(format!("Array {name} index out of bounds: {index}"));
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
         }
-        Ok(array[index])
     }
 
-    pub fn set_float_array(&mut self, name: &str, index: usize, value: f64) -> Result<(), String> {
-        if !self.float_arrays.contains_key(name) {
-            self.dim_float_array(name, 10);
+    /// Get the value of a variable or array element
+    fn evaluate_variable(&mut self, variable: &Variable) -> Result<Value, ErrorKind> {
+        let name = self.variable_name(variable);
+        if variable.subscripts.is_empty() {
+            return Ok(match variable.kind {
+                IdentifierKind::Float => Value::Number(self.get_float_scalar(&name)),
+                IdentifierKind::Integer => Value::Number(self.get_integer_scalar(&name).into()),
+                IdentifierKind::String => Value::String(self.get_string_scalar(&name)),
+            });
         }
-        // Safety: we just created this
-        #[allow(clippy::unwrap_used)]
-        let array = self.float_arrays.get_mut(name).unwrap();
-        if index >= array.len() {
-            return Err(format!("Array {name} index out of bounds: {index}"));
+        let subscripts = self.evaluate_subscripts(variable)?;
+        Ok(match variable.kind {
+            IdentifierKind::Float => Value::Number(self.get_float_array(&name, &subscripts)?),
+            IdentifierKind::Integer => {
+                Value::Number(self.get_integer_array(&name, &subscripts)?.into())
+            }
+            IdentifierKind::String => Value::String(self.get_string_array(&name, &subscripts)?),
+        })
+    }
+
+    /// Store a value in a variable or array element, which must be of the same type; a number
+    /// stored in an integer variable is truncated
+    pub fn assign(&mut self, variable: &Variable, value: Value) -> Result<(), ErrorKind> {
+        let result = self.store(variable, value);
+        self.locate(result, variable.span)
+    }
+
+    /// Store a value as [`Self::assign`] does, without locating its errors
+    fn store(&mut self, variable: &Variable, value: Value) -> Result<(), ErrorKind> {
+        let name = self.variable_name(variable);
+        let subscripts = if variable.subscripts.is_empty() {
+            None
+        } else {
+            Some(self.evaluate_subscripts(variable)?)
+        };
+        match (variable.kind, value, subscripts) {
+            (IdentifierKind::Float, Value::Number(number), None) => {
+                self.set_float_scalar(&name, number);
+            }
+            (IdentifierKind::Float, Value::Number(number), Some(subscripts)) => {
+                self.set_float_array(&name, &subscripts, number)?;
+            }
+            (IdentifierKind::Integer, Value::Number(number), None) => {
+                self.set_integer_scalar(&name, to_integer(number)?);
+            }
+            (IdentifierKind::Integer, Value::Number(number), Some(subscripts)) => {
+                self.set_integer_array(&name, &subscripts, to_integer(number)?)?;
+            }
+            (IdentifierKind::String, Value::String(string), None) => {
+                self.set_string_scalar(&name, string);
+            }
+            (IdentifierKind::String, Value::String(string), Some(subscripts)) => {
+                self.set_string_array(&name, &subscripts, string)?;
+            }
+            _ => return Err(ErrorKind::TypeMismatch),
         }
-        array[index] = value;
         Ok(())
     }
 
-    pub fn evaluate_expression(&self, node: &ParseNode) -> Result<f64, String> {
-        match node.get_node_type() {
-            // Safety: this is an invariant
-            #[allow(clippy::unwrap_used)]
-            NodeType::Number | NodeType::Float => Ok(node.get_value().parse().unwrap()),
-            NodeType::Identifier => Ok(self.get_float_scalar(node.get_value())),
-            NodeType::Expression => {
-                let children = node.get_children();
-                let left = self.evaluate_expression(&children[0])?;
-                let right = self.evaluate_expression(&children[2])?;
-                match children[1].get_value() {
-                    "+" => Ok(left + right),
-                    "-" => Ok(left - right),
-                    "*" => Ok(left * right),
-                    "/" => Ok(left / right),
-                    "^" => Ok(left.powf(right)),
-                    _ => Err(format!("Unexpected operator: {}", children[1].get_value())),
-                }
+    /// Apply a binary operator; comparisons and logical operators give 1 for true and 0 for
+    /// false, and strings may only be compared with strings or joined with `+`
+    fn evaluate_binary(operator: BinaryOp, left: Value, right: Value) -> Result<Value, ErrorKind> {
+        if let (BinaryOp::Add, Value::String(left), Value::String(right)) =
+            (operator, &left, &right)
+        {
+            if left.chars().count() + right.chars().count() > MAX_STRING_LENGTH {
+                return Err(ErrorKind::StringTooLong);
             }
-            _ => Err(format!("Unexpected node type: {:?}", node.get_node_type())),
+            return Ok(Value::String(format!("{left}{right}")));
         }
+        let ordering = match (&left, &right) {
+            (Value::Number(left), Value::Number(right)) => left.partial_cmp(right),
+            (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+            _ => return Err(ErrorKind::TypeMismatch),
+        };
+        match operator {
+            BinaryOp::Equal => return Ok(Value::from_bool(ordering == Some(Ordering::Equal))),
+            BinaryOp::NotEqual => return Ok(Value::from_bool(ordering != Some(Ordering::Equal))),
+            BinaryOp::Less => return Ok(Value::from_bool(ordering == Some(Ordering::Less))),
+            BinaryOp::Greater => return Ok(Value::from_bool(ordering == Some(Ordering::Greater))),
+            BinaryOp::LessEqual => {
+                return Ok(Value::from_bool(ordering.is_some_and(Ordering::is_le)))
+            }
+            BinaryOp::GreaterEqual => {
+                return Ok(Value::from_bool(ordering.is_some_and(Ordering::is_ge)))
+            }
+            _ => {}
+        }
+
+        let (Value::Number(left), Value::Number(right)) = (left, right) else {
+            return Err(ErrorKind::TypeMismatch);
+        };
+        let number = match operator {
+            BinaryOp::Add => left + right,
+            BinaryOp::Subtract => left - right,
+            BinaryOp::Multiply => left * right,
+            BinaryOp::Divide if right == 0.0 => return Err(ErrorKind::DivisionByZero),
+            BinaryOp::Divide => left / right,
+            BinaryOp::Power => left.powf(right),
+            BinaryOp::And => return Ok(Value::from_bool(left != 0.0 && right != 0.0)),
+            BinaryOp::Or => return Ok(Value::from_bool(left != 0.0 || right != 0.0)),
+            _ => unreachable!("comparisons are handled above"),
+        };
+        Ok(Value::Number(checked_number(number)?))
     }
 }
 
+/// Shorten a name to the first two characters, which are all that Applesoft looks at, and add
+/// its type suffix
+fn significant_name(variable: &Variable) -> String {
+    let significant: String = variable.name.chars().take(2).collect();
+    format!("{significant}{}", variable.kind.suffix())
+}
+
+/// Truncate a number to the 16-bit integer an integer variable holds; Applesoft does not
+/// allow -32768
+fn to_integer(number: f64) -> Result<i16, ErrorKind> {
+    let number = number.trunc();
+    if !(-32767.0..=32767.0).contains(&number) {
+        return Err(ErrorKind::IllegalQuantity);
+    }
+    // safe because of the check above
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(number as i16)
+}
+
+/// Get an element of an array; an array used before it is dimensioned gets 11 elements in each
+/// dimension, for subscripts 0 to 10
+fn array_element<'a, T: Element>(
+    arrays: &'a mut HashMap<String, Array<T>>,
+    name: &str,
+    subscripts: &[usize],
+) -> Result<&'a mut T, ErrorKind> {
+    if !arrays.contains_key(name) {
+        dim_array(arrays, name, vec![DEFAULT_SIZE; subscripts.len()])?;
+    }
+    // Safety: we just created this
+    #[allow(clippy::unwrap_used)]
+    arrays.get_mut(name).unwrap().element(subscripts)
+}
+
+/// Create an array with the given number of elements in each dimension
+fn dim_array<T: Element>(
+    arrays: &mut HashMap<String, Array<T>>,
+    name: &str,
+    sizes: Vec<usize>,
+) -> Result<(), ErrorKind> {
+    if arrays.contains_key(name) {
+        return Err(ErrorKind::RedimdArray);
+    }
+    let _unused = arrays.insert(name.to_owned(), Array::new(sizes)?);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use lpi_lexer::{
+        span::{Span, Spanned},
+        token::IdentifierKind,
+    };
+    use lpi_parser::ast::{Function, Line, Statement};
+
+    /// Give an expression an empty span
+    fn node(value: Expr) -> Spanned<Expr> {
+        Spanned {
+            value,
+            span: Span::default(),
+        }
+    }
+
+    /// Build a program holding the single statement `LET A = <value>`
+    fn let_program(value: Expr) -> Program {
+        Program {
+            lines: vec![Line {
+                number: Some(10),
+                statements: vec![Spanned {
+                    value: Statement::Let {
+                        variable: Variable {
+                            name: "A".to_owned(),
+                            kind: IdentifierKind::Float,
+                            subscripts: Vec::new(),
+                            span: Span::default(),
+                        },
+                        value: node(value),
+                    },
+                    span: Span::default(),
+                }],
+                span: Span::default(),
+            }],
+        }
+    }
+
+    /// Evaluate the value of the LET statement, and assign it to its variable
+    fn evaluate_let(interpreter: &mut Interpreter) -> (String, f64) {
+        #[allow(clippy::expect_used)]
+        let (line, index) = interpreter.fetch().expect("expected a statement");
+        let Statement::Let { variable, value } = &line.statements[index].value else {
+            panic!("expected a LET statement");
+        };
+        let name = interpreter.variable_name(variable);
+        #[allow(clippy::expect_used)]
+        let result = interpreter
+            .evaluate_expression(value)
+            .expect("Error evaluating expression");
+        interpreter.set_float_scalar(&name, result);
+        (name, result)
+    }
 
     #[test]
     #[allow(clippy::float_cmp)]
     fn test_evaluate_expression() {
-        let mut interpreter = Interpreter::new(ParseNode::new(
-            NodeType::Program,
-            String::new(),
-            vec![ParseNode::new(
-                NodeType::Line,
-                String::new(),
-                vec![ParseNode::new(
-                    NodeType::StatementName,
-                    "LET".to_owned(),
-                    vec![
-                        ParseNode::new(NodeType::Identifier, "A".to_owned(), vec![]),
-                        ParseNode::new(
-                            NodeType::Expression,
-                            String::new(),
-                            vec![
-                                ParseNode::new(NodeType::Number, "1".to_owned(), vec![]),
-                                ParseNode::new(NodeType::Symbol, "+".to_owned(), vec![]),
-                                ParseNode::new(NodeType::Number, "2".to_owned(), vec![]),
-                            ],
-                        ),
-                    ],
-                )],
-            )],
-        ));
-
-        let variable_node_value;
-        let result;
-        #[allow(clippy::unwrap_used, clippy::expect_used)]
-        {
-            let node = interpreter.root.get_children().first().unwrap();
-            let let_node = node.get_children().first().unwrap();
-            let variable_node = let_node.get_children().first().unwrap();
-            let expression_node = let_node.get_children().last().unwrap();
-            result = interpreter
-                .evaluate_expression(expression_node)
-                .expect("Error evaluating expression");
-            variable_node_value = variable_node.get_value().to_owned();
-        }
+        let mut interpreter = Interpreter::new(&let_program(Expr::Binary(
+            BinaryOp::Add,
+            Box::new(node(Expr::Number(1.0))),
+            Box::new(node(Expr::Number(2.0))),
+        )));
+
+        let (name, result) = evaluate_let(&mut interpreter);
         assert_eq!(result, 3.0);
-        interpreter.set_float_scalar(&variable_node_value, result);
-        assert_eq!(interpreter.get_float_scalar(&variable_node_value), 3.0);
+        assert_eq!(interpreter.get_float_scalar(&name), 3.0);
     }
 
     #[test]
     #[allow(clippy::float_cmp)]
     fn test_evaluate_expression_with_parens() {
-        let mut interpreter = Interpreter::new(ParseNode::new(
-            NodeType::Program,
-            String::new(),
-            vec![ParseNode::new(
-                NodeType::Line,
-                String::new(),
-                vec![ParseNode::new(
-                    NodeType::StatementName,
-                    "LET".to_owned(),
-                    vec![
-                        ParseNode::new(NodeType::Identifier, "A".to_owned(), vec![]),
-                        ParseNode::new(
-                            NodeType::Expression,
-                            String::new(),
-                            vec![
-                                ParseNode::new(NodeType::Number, "1".to_owned(), vec![]),
-                                ParseNode::new(NodeType::Symbol, "+".to_owned(), vec![]),
-                                ParseNode::new(
-                                    NodeType::Expression,
-                                    String::new(),
-                                    vec![
-                                        ParseNode::new(NodeType::Number, "2".to_owned(), vec![]),
-                                        ParseNode::new(NodeType::Symbol, "*".to_owned(), vec![]),
-                                        ParseNode::new(NodeType::Number, "3".to_owned(), vec![]),
-                                    ],
-                                ),
-                            ],
-                        ),
-                    ],
-                )],
-            )],
-        ));
-
-        let variable_node_value;
-        let result;
-        #[allow(clippy::unwrap_used, clippy::expect_used)]
-        {
-            let node = interpreter.root.get_children().first().unwrap();
-            let let_node = node.get_children().first().unwrap();
-            let variable_node = let_node.get_children().first().unwrap();
-            let expression_node = let_node.get_children().last().unwrap();
-            result = interpreter
-                .evaluate_expression(expression_node)
-                .expect("Error evaluating expression");
-            variable_node_value = variable_node.get_value().to_owned();
-        }
+        let mut interpreter = Interpreter::new(&let_program(Expr::Binary(
+            BinaryOp::Add,
+            Box::new(node(Expr::Number(1.0))),
+            Box::new(node(Expr::Binary(
+                BinaryOp::Multiply,
+                Box::new(node(Expr::Number(2.0))),
+                Box::new(node(Expr::Number(3.0))),
+            ))),
+        )));
+
+        let (name, result) = evaluate_let(&mut interpreter);
         assert_eq!(result, 7.0);
-        interpreter.set_float_scalar(&variable_node_value, result);
-        assert_eq!(interpreter.get_float_scalar(&variable_node_value), 7.0);
+        assert_eq!(interpreter.get_float_scalar(&name), 7.0);
+    }
+
+    #[test]
+    fn test_function_with_wrong_number_of_arguments() {
+        // an expression built by hand is not checked by the parser
+        let mut interpreter =
+            Interpreter::new(&let_program(Expr::Function(Function::Left, vec![])));
+        let Err(error) = interpreter.interpret(&mut Vec::new()) else {
+            panic!("expected LEFT$ without arguments to stop with an error");
+        };
+        assert_eq!(error.to_string(), "?SYNTAX ERROR IN 10");
+    }
+
+    #[test]
+    fn test_fetch_in_line_number_order() {
+        let source = "30 END\n10 A=1:A=2\n20 A=3\n30 A=4";
+        #[allow(clippy::expect_used)]
+        let program = lpi_parser::parse(&lpi_lexer::lex(source)).expect("Error parsing program");
+        let mut interpreter = Interpreter::new(&program);
+
+        let mut fetched = Vec::new();
+        while let Some((line, index)) = interpreter.fetch() {
+            fetched.push((line.number, index));
+        }
+        // the second line 30 replaces the first
+        assert_eq!(
+            fetched,
+            vec![(Some(10), 0), (Some(10), 1), (Some(20), 0), (Some(30), 0)]
+        );
+        assert_eq!(
+            interpreter.counter,
+            ProgramCounter {
+                line: Some(3),
+                statement: 0
+            }
+        );
     }
 }
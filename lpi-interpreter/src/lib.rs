@@ -2,38 +2,822 @@
 //!
 //! The interpreter takes the output of the parser and builds commands that can be run to interpret the program.
 
-use lpi_parser::parse_node::ParseNode;
+use def::def_statement;
+use dim::dim_statement;
+use error::{Error, ErrorKind};
+use gosub::{gosub_statement, on_gosub_statement, pop_statement, return_statement};
+use goto::{goto_statement, on_goto_statement};
+use lpi_parser::ast::{Program, Statement};
+use print::print_statement;
+use r#for::{for_statement, next_statement};
+use r#if::if_statement;
 use r#let::let_statement;
-use std::{collections::HashMap, io::Write};
+use read::{read_statement, restore_statement};
+use std::io::Write;
 
+pub use interpreter::Interpreter;
+
+mod array;
+mod def;
+mod dim;
+pub mod error;
+mod r#for;
+mod function;
+mod gosub;
+mod goto;
+mod r#if;
 mod interpreter;
 mod r#let;
+mod print;
+mod random;
+mod read;
+mod value;
 
-/// Interpret the parsed tokens
+/// Interpret the parsed program with the default options.
+///
+/// The numbered lines are stored in line number order. Lines without a number are run first in
+/// immediate mode, in source order, as if they were typed in once the numbered lines had been
+/// entered. The program is then run from its first line, unless one of them has gone into it.
 /// # Errors
-/// Returns an error if there is a problem writing to the output
-pub fn interpret<T: Write>(
-    output: &mut T,
-    root: &ParseNode,
-) -> Result<(), Box<dyn std::error::Error>> {
-    writeln!(output, "Interpreting...")?;
-
-    let mut interpreter = interpreter::Interpreter::new(root.clone());
-
-    let mut lines: HashMap<u16, &ParseNode> = HashMap::new();
-    for line in root.get_children() {
-        if let Some(line_number) = line.get_children().first() {
-            if let Ok(line_number) = line_number.get_value().parse::<u16>() {
-                let _unused = lines.insert(line_number, line);
-            } else {
-                writeln!(output, "Error: bad Line number not found: {line_number}")?;
+/// Returns the Applesoft error that stopped the program, such as `?SYNTAX ERROR IN 10` when it
+/// runs a line holding a syntax error, or a failure writing to the output
+pub fn interpret<T: Write>(output: &mut T, program: &Program) -> Result<(), Error> {
+    Interpreter::new(program).interpret(output)
+}
+
+/// Run statements from the program counter until execution stops
+fn run<T: Write>(interpreter: &mut Interpreter, output: &mut T) -> Result<(), Error> {
+    while let Some((line, index)) = interpreter.fetch() {
+        let statement = &line.statements[index];
+        execute(interpreter, output, &statement.value).map_err(|kind| {
+            let (error_line, error_span) = interpreter.take_error_location();
+            Error {
+                kind,
+                line: error_line.or(line.number),
+                span: error_span.unwrap_or(statement.span),
             }
+        })?;
+    }
+    Ok(())
+}
+
+/// Run a single statement
+fn execute<T: Write>(
+    interpreter: &mut Interpreter,
+    output: &mut T,
+    statement: &Statement,
+) -> Result<(), ErrorKind> {
+    match statement {
+        Statement::Let { variable, value } => let_statement(interpreter, variable, value),
+        Statement::Print(items) => print_statement(interpreter, output, items),
+        Statement::End => {
+            interpreter.end();
+            Ok(())
         }
+        Statement::Def {
+            name,
+            parameter,
+            body,
+        } => def_statement(interpreter, name, parameter, body),
+        Statement::Dim(arrays) => dim_statement(interpreter, arrays),
+        Statement::For {
+            variable,
+            start,
+            end,
+            step,
+        } => for_statement(interpreter, variable, start, end, step.as_ref()),
+        Statement::Next(variables) => next_statement(interpreter, variables),
+        Statement::If(condition) => if_statement(interpreter, condition),
+        Statement::Goto(target) => goto_statement(interpreter, *target),
+        Statement::OnGoto { index, targets } => on_goto_statement(interpreter, index, targets),
+        Statement::Gosub(target) => gosub_statement(interpreter, *target),
+        Statement::OnGosub { index, targets } => on_gosub_statement(interpreter, index, targets),
+        Statement::Return => return_statement(interpreter),
+        Statement::Pop => pop_statement(interpreter),
+        Statement::Read(variables) => read_statement(interpreter, variables),
+        Statement::Restore => restore_statement(interpreter),
+        Statement::Rem(_) | Statement::Data(_) => Ok(()),
+        Statement::Error { .. } => Err(ErrorKind::Syntax),
     }
+}
 
-    for line in root.get_children() {
-        writeln!(output, "Line: {line}")?;
-        let_statement(&mut interpreter, line)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str) -> String {
+        #[allow(clippy::expect_used)]
+        let parse = lpi_parser::parse(&lpi_lexer::lex(source)).expect("Error parsing program");
+        let mut output = Vec::new();
+        #[allow(clippy::expect_used)]
+        interpret(&mut output, &parse).expect("Error interpreting program");
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    #[test]
+    fn test_hello_world() {
+        assert_eq!(run("10 PRINT \"HELLO, WORLD!\"\n20 END"), "HELLO, WORLD!\n");
+    }
+
+    #[test]
+    fn test_multiple_statements() {
+        assert_eq!(run("10 A=1:B=2:PRINT A+B"), "3\n");
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            run("10 PRINT 2*3^2;\" \";(1+2)*3;\" \";8-4-2;\" \";2^3^2"),
+            "18 9 2 64\n"
+        );
+    }
+
+    #[test]
+    fn test_unary_operators() {
+        assert_eq!(
+            run("10 A=-5:B=3:PRINT A;\" \";2*-B;\" \";-2^2;\" \";+A;\" \";NOT A;\" \";NOT 0"),
+            "-5 -6 -4 -5 0 1\n"
+        );
+    }
+
+    #[test]
+    fn test_relational_operators() {
+        assert_eq!(
+            run("10 A=2:PRINT A=2;A<>2;A<3;A>3;A<=2;A>=3;A=<1;A=>2;A><2"),
+            "101010010\n"
+        );
+    }
+
+    #[test]
+    fn test_string_comparisons() {
+        assert_eq!(
+            run("10 PRINT \"A\"<\"B\";\"ABC\"=\"ABC\";\"B\"<\"AB\";\"\"<\"A\";\"A\"<>\"A\""),
+            "11010\n"
+        );
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        assert_eq!(
+            run("10 A=3:PRINT A AND 0;A AND -1;0 OR 0;A OR 0;A>1 AND A<5;NOT A=3"),
+            "010110\n"
+        );
+    }
+
+    /// Run the source, which may hold syntax errors, until it stops with an error
+    fn run_to_error(source: &str) -> (String, String) {
+        let program = lpi_parser::parse_with_recovery(&lpi_lexer::lex(source));
+        let mut output = Vec::new();
+        let Err(error) = interpret(&mut output, &program) else {
+            panic!("expected {source} to stop with an error");
+        };
+        (
+            String::from_utf8_lossy(&output).into_owned(),
+            error.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_comparing_string_to_number() {
+        let (_, error) = run_to_error("10 PRINT \"A\" = 1");
+        assert_eq!(error, "?TYPE MISMATCH ERROR IN 10");
+    }
+
+    #[test]
+    fn test_syntax_error_when_run() {
+        // the statements before the error still run
+        let (output, error) = run_to_error("10 PRINT 1\n20 PRINT 2: A = ): PRINT 3\n30 PRINT 4");
+        assert_eq!(output, "1\n2\n");
+        assert_eq!(error, "?SYNTAX ERROR IN 20");
+    }
+
+    #[test]
+    fn test_error_span() {
+        let error_span = |source: &str| {
+            let program = lpi_parser::parse_with_recovery(&lpi_lexer::lex(source));
+            let Err(error) = interpret(&mut Vec::new(), &program) else {
+                panic!("expected {source} to stop with an error");
+            };
+            (
+                error.span.line,
+                error.span.column,
+                source[error.span.start..error.span.end].to_owned(),
+            )
+        };
+        // the error points at the innermost operand that raised it
+        assert_eq!(
+            error_span("10 PRINT 1\n20 PRINT 2: X = 2 + 1/0: PRINT 3"),
+            (2, 21, "1/0".to_owned())
+        );
+        assert_eq!(
+            error_span("10 DIM A(2):X = A(1) + A(3)"),
+            (1, 24, "A(3)".to_owned())
+        );
+        assert_eq!(error_span("10 A(20) = 1"), (1, 4, "A(20)".to_owned()));
+        assert_eq!(
+            error_span("10 PRINT LEN(\"A\" + 1)"),
+            (1, 14, "\"A\" + 1".to_owned())
+        );
+        // an error that belongs to no operand points at the statement
+        assert_eq!(error_span("10 RETURN"), (1, 4, "RETURN".to_owned()));
+        // a bad DATA item points at its DATA statement
+        assert_eq!(
+            error_span("10 READ A\n20 PRINT: DATA ABC"),
+            (2, 11, "DATA ABC".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_not_run() {
+        let program =
+            lpi_parser::parse_with_recovery(&lpi_lexer::lex("10 PRINT 1\n20 END\n30 A = )"));
+        let mut output = Vec::new();
+        assert!(interpret(&mut output, &program).is_ok());
+        assert_eq!(output, b"1\n");
+    }
+
+    #[test]
+    fn test_lines_run_in_number_order() {
+        assert_eq!(
+            run("30 PRINT 3\n10 PRINT 1\n20 PRINT 2:END:PRINT 9\n30 PRINT 4"),
+            "1\n2\n"
+        );
+        assert_eq!(run("20 PRINT 2\n10 PRINT 1\n20 PRINT 3"), "1\n3\n");
+    }
+
+    #[test]
+    fn test_immediate_mode() {
+        // the lines without a number run first, then the program runs as if RUN were typed
+        assert_eq!(run("10 PRINT 1\nA = 2\nPRINT A + 1"), "3\n1\n");
+        assert_eq!(run("10 PRINT 1\nREM NOTE\n20 PRINT 2"), "1\n2\n");
+    }
+
+    #[test]
+    fn test_bad_line_number() {
+        // a line number out of range makes a bad direct line, which stops before the program runs
+        let source = "10 PRINT 1\n64000 PRINT 3\n20 PRINT 2";
+        let (output, error) = run_to_error(source);
+        assert_eq!((output.as_str(), error.as_str()), ("", "?SYNTAX ERROR"));
+        let program = lpi_parser::parse_with_recovery(&lpi_lexer::lex(source));
+        let Err(error) = interpret(&mut Vec::new(), &program) else {
+            panic!("expected {source} to stop with an error");
+        };
+        assert_eq!(error.span.line, 2);
+    }
+
+    #[test]
+    fn test_goto() {
+        assert_eq!(
+            run("10 PRINT 1\n20 GOTO 40\n30 PRINT 3\n40 PRINT 4"),
+            "1\n4\n"
+        );
+        // a loop that counts down
+        assert_eq!(
+            run("10 A = 3\n20 PRINT A;\n30 A = A - 1\n40 ON (A = 0) + 1 GOTO 20, 50\n50 END"),
+            "321"
+        );
+    }
+
+    #[test]
+    fn test_on_goto_falls_through() {
+        let source = "10 ON I GOTO 100, 200\n20 PRINT 0\n30 END\n100 PRINT 1\n200 PRINT 2";
+        assert_eq!(run(&format!("5 I = 0\n{source}")), "0\n");
+        assert_eq!(run(&format!("5 I = 1.9\n{source}")), "1\n2\n");
+        assert_eq!(run(&format!("5 I = 2\n{source}")), "2\n");
+        assert_eq!(run(&format!("5 I = 3\n{source}")), "0\n");
+        let (_, error) = run_to_error(&format!("5 I = -1\n{source}"));
+        assert_eq!(error, "?ILLEGAL QUANTITY ERROR IN 10");
+    }
+
+    #[test]
+    fn test_undefined_statement() {
+        let (output, error) = run_to_error("10 PRINT 1\n20 GOTO 25\n30 PRINT 3");
+        assert_eq!(output, "1\n");
+        assert_eq!(error, "?UNDEF'D STATEMENT ERROR IN 20");
+        let (_, error) = run_to_error("10 ON 2 GOTO 10, 15");
+        assert_eq!(error, "?UNDEF'D STATEMENT ERROR IN 10");
+    }
+
+    #[test]
+    fn test_gosub() {
+        // RETURN goes back to the statement after the GOSUB, even in the middle of a line
+        let source = "10 GOSUB 100: PRINT 2: GOSUB 200\n20 END\n100 PRINT 1\n110 RETURN\n\
+                      200 PRINT 3: GOSUB 100: RETURN";
+        assert_eq!(run(source), "1\n2\n3\n1\n");
+    }
+
+    #[test]
+    fn test_on_gosub() {
+        let source =
+            "20 ON I GOSUB 100, 200: PRINT 0\n30 END\n100 PRINT 1: RETURN\n200 PRINT 2: RETURN";
+        assert_eq!(run(&format!("10 I = 0\n{source}")), "0\n");
+        assert_eq!(run(&format!("10 I = 2\n{source}")), "2\n0\n");
+    }
+
+    #[test]
+    fn test_pop() {
+        // POP forgets the newest return, so RETURN goes back to the GOSUB before it
+        let source = "10 GOSUB 100: PRINT 3\n20 END\n100 GOSUB 200: PRINT 9\n\
+                      200 PRINT 1: POP: RETURN";
+        assert_eq!(run(source), "1\n3\n");
+    }
+
+    #[test]
+    fn test_return_without_gosub() {
+        let (_, error) = run_to_error("10 GOSUB 20\n20 RETURN");
+        assert_eq!(error, "?RETURN WITHOUT GOSUB ERROR IN 20");
+        let (_, error) = run_to_error("10 POP");
+        assert_eq!(error, "?RETURN WITHOUT GOSUB ERROR IN 10");
+    }
+
+    #[test]
+    fn test_runaway_recursion() {
+        let (output, error) = run_to_error("10 A = A + 1: GOSUB 20\n20 PRINT A;\" \";: GOTO 10");
+        assert_eq!(error, "?OUT OF MEMORY ERROR IN 10");
+        assert!(output.ends_with(" 23 24 "), "{output}");
+    }
+
+    #[test]
+    fn test_print_separators() {
+        assert_eq!(
+            run("10 PRINT 1;2;\"A\"3\n20 ? \"B\";\n30 PRINT"),
+            "12A3\nB\n"
+        );
+        assert_eq!(run("10 PRINT 1,2,3"), "1               2               3\n");
+        // past the start of the last zone, a comma moves to the next line
+        assert_eq!(
+            run("10 PRINT \"ABCDEFGHIJKLMNOPQRSTUVWXY\",1"),
+            "ABCDEFGHIJKLMNOPQRSTUVWXY\n1\n"
+        );
+        assert_eq!(run("10 PRINT \"AB\",:PRINT \"C\""), "AB              C\n");
+        assert_eq!(
+            run("10 PRINT TAB(5);\"A\";TAB(3);\"B\"SPC(2)\"C\""),
+            "    AB  C\n"
+        );
+        assert_eq!(run("10 PRINT SPC(3)"), "   ");
+        assert_eq!(
+            run_to_error("10 PRINT TAB(256)"),
+            (String::new(), "?ILLEGAL QUANTITY ERROR IN 10".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_print_numbers() {
+        assert_eq!(
+            run("10 PRINT -1;\" \";.5;\" \";-0.25;\" \";1E10;\" \";1/3;\" \";2/3"),
+            "-1 .5 -.25 1E+10 .333333333 .666666667\n"
+        );
+        assert_eq!(
+            run("10 PRINT 123456789;\" \";1234567890;\" \";.01;\" \";.001;\" \";100;\" \";12.5"),
+            "123456789 1.23456789E+09 .01 1E-03 100 12.5\n"
+        );
+    }
+
+    #[test]
+    fn test_for_next() {
+        assert_eq!(
+            run("10 FOR I = 1 TO 3: PRINT I;: NEXT I: PRINT I"),
+            "1234\n"
+        );
+        assert_eq!(
+            run("10 FOR I = 10 TO 1 STEP -3: PRINT I;: NEXT: PRINT"),
+            "10741\n"
+        );
+        assert_eq!(
+            run("10 FOR I = 0 TO 1 STEP 0.25: PRINT I;\" \";: NEXT"),
+            "0 .25 .5 .75 1 "
+        );
+        // the limit and step are evaluated once, when the loop starts
+        assert_eq!(run("10 N = 2: FOR I = 1 TO N: N = 5: PRINT I;: NEXT"), "12");
+    }
+
+    #[test]
+    fn test_for_body_runs_once() {
+        assert_eq!(run("10 FOR I = 5 TO 1: PRINT I;: NEXT: PRINT I"), "56\n");
+    }
+
+    #[test]
+    fn test_nested_loops() {
+        let source = "10 FOR I = 1 TO 2: FOR J = 1 TO 3\n20 PRINT I * 10 + J;\" \";\n30 NEXT J, I";
+        assert_eq!(run(source), "11 12 13 21 22 23 ");
+        // NEXT with no variable steps the innermost loop
+        let source = "10 FOR I = 1 TO 2: FOR J = 1 TO 2: PRINT I;J;\" \";: NEXT: NEXT";
+        assert_eq!(run(source), "11 12 21 22 ");
+    }
+
+    #[test]
+    fn test_reused_loop_variable() {
+        // the second FOR I replaces the first, and the loop on J inside it
+        let source = "10 FOR I = 1 TO 9: FOR J = 1 TO 9: FOR I = 1 TO 2: PRINT I;: NEXT I\n\
+                      20 NEXT J";
+        let (output, error) = run_to_error(source);
+        assert_eq!(output, "12");
+        assert_eq!(error, "?NEXT WITHOUT FOR ERROR IN 20");
+    }
+
+    #[test]
+    fn test_next_without_for() {
+        let (_, error) = run_to_error("10 NEXT");
+        assert_eq!(error, "?NEXT WITHOUT FOR ERROR IN 10");
+        let (output, error) = run_to_error("10 FOR I = 1 TO 2: PRINT I;\n20 NEXT J");
+        assert_eq!(output, "1");
+        assert_eq!(error, "?NEXT WITHOUT FOR ERROR IN 20");
+        // a subroutine cannot step the loops of its caller
+        let (_, error) = run_to_error("10 FOR I = 1 TO 2: GOSUB 20\n20 NEXT I");
+        assert_eq!(error, "?NEXT WITHOUT FOR ERROR IN 20");
+    }
+
+    #[test]
+    fn test_return_closes_loops() {
+        let source = "10 FOR I = 1 TO 2: GOSUB 100: NEXT I: END\n\
+                      100 FOR J = 1 TO 9: PRINT I;J;\" \";: RETURN";
+        assert_eq!(run(source), "11 21 ");
+    }
+
+    #[test]
+    fn test_too_many_loops() {
+        let loops: String = ('A'..='K')
+            .map(|name| format!("FOR {name} = 1 TO 2: "))
+            .collect();
+        let (_, error) = run_to_error(&format!("10 {loops}PRINT"));
+        assert_eq!(error, "?OUT OF MEMORY ERROR IN 10");
+        let loops: String = ('A'..='J')
+            .map(|name| format!("FOR {name} = 1 TO 1: "))
+            .collect();
+        assert_eq!(run(&format!("10 {loops}PRINT 1;: NEXT")), "1");
+    }
+
+    #[test]
+    fn test_if() {
+        let source = "10 IF B THEN PRINT 1: PRINT 2\n20 PRINT 3";
+        assert_eq!(run(&format!("5 B = 1\n{source}")), "1\n2\n3\n");
+        // a false condition skips the rest of the line, not just the next statement
+        assert_eq!(run(&format!("5 B = 0\n{source}")), "3\n");
+        assert_eq!(
+            run("10 A = 5: IF A > 2 AND A < 9 THEN PRINT \"IN\""),
+            "IN\n"
+        );
+    }
+
+    #[test]
+    fn test_if_goto() {
+        let source = "10 IF A = 1 THEN 100\n20 IF A = 2 GOTO 200\n30 PRINT 0: END\n\
+                      100 PRINT 1: END\n200 PRINT 2";
+        assert_eq!(run(&format!("5 A = 1\n{source}")), "1\n");
+        assert_eq!(run(&format!("5 A = 2\n{source}")), "2\n");
+        assert_eq!(run(&format!("5 A = 3\n{source}")), "0\n");
+    }
+
+    #[test]
+    fn test_if_in_loop() {
+        assert_eq!(
+            run("10 FOR I = 1 TO 6: IF I > 3 THEN PRINT I;\n20 NEXT"),
+            "456"
+        );
+        let source = "10 FOR I = 1 TO 5: IF I > 3 THEN NEXT: END\n20 PRINT I;: NEXT";
+        assert_eq!(run(source), "123");
+    }
+
+    #[test]
+    fn test_if_in_immediate_mode() {
+        assert_eq!(run("B = 0: IF B THEN PRINT 1\nPRINT 2"), "2\n");
+    }
+
+    #[test]
+    fn test_if_string_condition() {
+        let (_, error) = run_to_error("10 IF \"A\" THEN PRINT 1");
+        assert_eq!(error, "?TYPE MISMATCH ERROR IN 10");
+    }
+
+    #[test]
+    fn test_goto_in_immediate_mode() {
+        assert_eq!(run("10 PRINT 1\n20 PRINT 2\nGOTO 20"), "2\n");
+    }
+
+    #[test]
+    fn test_array_elements() {
+        assert_eq!(run("10 A(2)=5:A(3)=A(2)*2:PRINT A(1+2)"), "10\n");
+    }
+
+    #[test]
+    fn test_dim() {
+        let source = "10 DIM A(3,2,1),B$(20),C%(4)\n20 A(3,2,1) = 5:B$(20) = \"X\":C%(4) = 7:A(0,0,0) = 1\n30 PRINT A(3,2,1);A(0,0,0);A(1,1,1);B$(20);C%(4)";
+        assert_eq!(run(source), "510X7\n");
+        // an array used before it is dimensioned has subscripts 0 to 10 in each dimension
+        assert_eq!(
+            run("10 A(10,10) = 1:B$(10) = \"Y\":PRINT A(10,10);B$(10);C%(10)"),
+            "1Y0\n"
+        );
+        assert_eq!(
+            run_to_error("10 A(1) = 1\n20 A(11) = 2"),
+            (String::new(), "?BAD SUBSCRIPT ERROR IN 20".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_dim_errors() {
+        let error = |source| run_to_error(source).1;
+        assert_eq!(error("10 DIM A(5):DIM A(5)"), "?REDIM'D ARRAY ERROR IN 10");
+        assert_eq!(
+            error("10 A$(1) = \"\":DIM A$(5)"),
+            "?REDIM'D ARRAY ERROR IN 10"
+        );
+        assert_eq!(error("10 DIM A(5):A(6) = 1"), "?BAD SUBSCRIPT ERROR IN 10");
+        assert_eq!(
+            error("10 DIM A(5,5):A(1) = 1"),
+            "?BAD SUBSCRIPT ERROR IN 10"
+        );
+        assert_eq!(
+            error("10 A(1,1) = 1:PRINT A(1)"),
+            "?BAD SUBSCRIPT ERROR IN 10"
+        );
+        assert_eq!(error("10 DIM A%(-1)"), "?ILLEGAL QUANTITY ERROR IN 10");
+        assert_eq!(error("10 DIM A(100,100)"), "?OUT OF MEMORY ERROR IN 10");
+        // arrays of different types do not share a name
+        assert_eq!(
+            run("10 DIM A(2),A%(3),A$(4):A$(4) = \"OK\":PRINT A$(4)"),
+            "OK\n"
+        );
+    }
+
+    #[test]
+    fn test_numeric_functions() {
+        let source = "10 PRINT ABS(-2.5);\" \";SGN(-3);SGN(0);SGN(.1);\" \";INT(2.7);\" \";INT(-2.5);\" \";SQR(16)";
+        assert_eq!(run(source), "2.5 -101 2 -3 4\n");
+        let source = "10 PRINT EXP(0);\" \";LOG(EXP(2));\" \";SIN(0);\" \";COS(0);\" \";TAN(0);\" \";ATN(1)*4";
+        assert_eq!(run(source), "1 2 0 1 0 3.14159265\n");
+    }
+
+    #[test]
+    fn test_numeric_function_errors() {
+        let error = |source| run_to_error(source).1;
+        assert_eq!(error("10 PRINT SQR(-1)"), "?ILLEGAL QUANTITY ERROR IN 10");
+        assert_eq!(error("10 PRINT LOG(0)"), "?ILLEGAL QUANTITY ERROR IN 10");
+        assert_eq!(error("10 PRINT LOG(-1)"), "?ILLEGAL QUANTITY ERROR IN 10");
+        assert_eq!(error("10 PRINT EXP(100)"), "?OVERFLOW ERROR IN 10");
+        assert_eq!(error("10 A = 1E30:A = A*A"), "?OVERFLOW ERROR IN 10");
+        assert_eq!(error("10 PRINT 1/0"), "?DIVISION BY ZERO ERROR IN 10");
+        assert_eq!(error("10 PRINT ABS(\"1\")"), "?TYPE MISMATCH ERROR IN 10");
+    }
+
+    #[test]
+    fn test_string_functions() {
+        let source = "10 A$ = \"HELLO\"\n20 PRINT LEN(A$);LEFT$(A$,2);\" \";RIGHT$(A$,3);\" \";MID$(A$,2,3);\" \";MID$(A$,4)";
+        assert_eq!(run(source), "5HE LLO ELL LO\n");
+        let source =
+            "10 PRINT LEFT$(\"AB\",0);LEFT$(\"AB\",9);RIGHT$(\"AB\",9);MID$(\"AB\",3);LEN(\"\")";
+        assert_eq!(run(source), "ABAB0\n");
+        let source = "10 PRINT STR$(.5);STR$(-1E10);\" \";ASC(\"A\");CHR$(66);ASC(CHR$(0))";
+        assert_eq!(run(source), ".5-1E+10 65B0\n");
+    }
+
+    #[test]
+    fn test_high_character_codes() {
+        // characters above 127 round-trip and count as one character each
+        let source =
+            "10 FOR N=0 TO 255:IF ASC(CHR$(N))<>N THEN PRINT N\n20 NEXT:PRINT ASC(CHR$(200))";
+        assert_eq!(run(source), "200\n");
+        let source = "10 B$=CHR$(200):FOR I=1 TO 7:B$=B$+B$:NEXT\n20 B$=B$+LEFT$(B$,127):PRINT LEN(B$)\n30 B$=B$+CHR$(128)";
+        assert_eq!(
+            run_to_error(source),
+            (
+                "255\n".to_owned(),
+                "?STRING TOO LONG ERROR IN 30".to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_val() {
+        let source = "10 PRINT VAL(\"12AB\");\" \";VAL(\" -1 2.5E1X\");\" \";VAL(\"ABC\");\" \";VAL(\".5E\");\" \";VAL(\"\")";
+        assert_eq!(run(source), "12 -125 0 .5 0\n");
+    }
+
+    #[test]
+    fn test_string_function_errors() {
+        let error = |source| run_to_error(source).1;
+        assert_eq!(error("10 PRINT ASC(\"\")"), "?ILLEGAL QUANTITY ERROR IN 10");
+        assert_eq!(
+            error("10 PRINT MID$(\"A\",0)"),
+            "?ILLEGAL QUANTITY ERROR IN 10"
+        );
+        assert_eq!(
+            error("10 PRINT LEFT$(\"A\",256)"),
+            "?ILLEGAL QUANTITY ERROR IN 10"
+        );
+        assert_eq!(error("10 PRINT CHR$(-1)"), "?ILLEGAL QUANTITY ERROR IN 10");
+        assert_eq!(error("10 PRINT LEN(1)"), "?TYPE MISMATCH ERROR IN 10");
+        assert_eq!(error("10 PRINT STR$(\"1\")"), "?TYPE MISMATCH ERROR IN 10");
+        assert_eq!(error("10 PRINT VAL(\"1E99\")"), "?OVERFLOW ERROR IN 10");
+    }
+
+    /// Run a program with the random numbers seeded
+    fn run_seeded(source: &str, seed: u64) -> String {
+        #[allow(clippy::expect_used)]
+        let program = lpi_parser::parse(&lpi_lexer::lex(source)).expect("Error parsing program");
+        let mut interpreter = Interpreter::new(&program);
+        interpreter.set_seed(seed);
+        let mut output = Vec::new();
+        #[allow(clippy::expect_used)]
+        interpreter
+            .interpret(&mut output)
+            .expect("Error interpreting program");
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_rnd() {
+        let source = "10 FOR I = 1 TO 5:PRINT RND(1);\" \";:NEXT:PRINT RND(0)";
+        let output = run_seeded(source, 1);
+        assert_eq!(output, run_seeded(source, 1));
+        assert_ne!(output, run_seeded(source, 2));
+        let numbers: Vec<f64> = output
+            .split_whitespace()
+            .map(|number| number.parse().unwrap_or(-1.0))
+            .collect();
+        assert!(numbers.iter().all(|number| (0.0..1.0).contains(number)));
+        // RND(0) gives the last number again
+        assert_eq!(numbers[4], numbers[5]);
+        // the numbers after the same negative argument are the same
+        let source =
+            "10 A = RND(-3):B = RND(1):C = RND(-3):D = RND(1):PRINT A = C AND B = D;A <> B";
+        assert_eq!(run(source), "11\n");
+        let source =
+            "10 FOR I = 1 TO 100:N = INT(RND(1)*6)+1:IF N < 1 OR N > 6 THEN PRINT N\n20 NEXT";
+        assert_eq!(run(source), "");
+    }
+
+    #[test]
+    fn test_def_fn() {
+        let source = "10 DEF FN F(X) = X*X+1:PRINT FN F(3);\" \";FN F(FN F(1))";
+        assert_eq!(run(source), "10 5\n");
+        // the parameter holds the argument only during the call, and other variables are
+        // global
+        let source =
+            "10 X = 7:Y = 2:DEF FN A(X) = X+Y\n20 PRINT FN A(1);\" \";X:Y = 5:PRINT FN A(X)";
+        assert_eq!(run(source), "3 7\n12\n");
+        // names are shortened like the names of variables, and DEF can replace a function
+        let source = "10 DEF FN SQUARE(X) = X*X:PRINT FN SQ(3):DEF FN SQ(X) = 0:PRINT FN SQUARE(3)";
+        assert_eq!(run(source), "9\n0\n");
+    }
+
+    #[test]
+    fn test_def_fn_errors() {
+        assert_eq!(
+            run_to_error("10 PRINT FN F(1)"),
+            (String::new(), "?UNDEF'D FUNCTION ERROR IN 10".to_owned())
+        );
+        assert_eq!(
+            run_to_error("10 DEF FN F(X) = X\nDEF FN G(X) = X"),
+            (String::new(), "?ILLEGAL DIRECT ERROR".to_owned())
+        );
+        // a function defined by a program can be called in immediate mode
+        assert_eq!(run("10 DEF FN F(X) = X+1\nGOTO 10\nPRINT FN F(1)"), "2\n");
+        assert_eq!(
+            run_to_error("10 DEF FN F(X) = X\n20 PRINT FN F(\"A\")"),
+            (String::new(), "?TYPE MISMATCH ERROR IN 20".to_owned())
+        );
+        // a function that keeps calling itself runs out of stack instead of crashing
+        assert_eq!(
+            run_to_error("10 DEF FN F(X) = FN F(X):PRINT FN F(1)"),
+            (String::new(), "?OUT OF MEMORY ERROR IN 10".to_owned())
+        );
+        assert_eq!(
+            run_to_error("10 DEF FN F(X) = FN G(X)+1:DEF FN G(X) = FN F(X)\n20 PRINT FN F(1)"),
+            (String::new(), "?OUT OF MEMORY ERROR IN 20".to_owned())
+        );
+        // calls that are not nested too deeply are fine
+        let source = "10 DEF FN A(X) = X+1:DEF FN B(X) = FN A(FN A(X)):PRINT FN B(FN B(1))";
+        assert_eq!(run(source), "5\n");
+    }
+
+    #[test]
+    fn test_read_data() {
+        // items are read in line order, whichever statement they are in
+        let source = "30 DATA 4.5,-1E2\n10 READ A,B$,C,D$:PRINT A;B$;C;D$;\"!\":READ E%,F\n20 PRINT E%;F:DATA 3, \"A, B:C\",,  D E";
+        assert_eq!(run(source), "3A, B:C0D E!\n4-100\n");
+        let source = "10 DATA 1,2\n20 READ A,B:RESTORE:READ C:PRINT A;B;C\n30 DATA 3";
+        assert_eq!(run(source), "121\n");
+        // a number can be read as a string, and each empty DATA statement holds one empty item
+        let source = "10 DATA 12\n20 DATA\n30 DATA\n40 READ A$,B,C$:PRINT A$;B;C$;LEN(C$)";
+        assert_eq!(run(source), "1200\n");
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert_eq!(
+            run_to_error("10 DATA 1\n20 READ A,B"),
+            (String::new(), "?OUT OF DATA ERROR IN 20".to_owned())
+        );
+        // an item that is not a number is reported against the DATA line
+        assert_eq!(
+            run_to_error("10 READ A:PRINT A:READ B\n20 DATA 1,ABC"),
+            ("1\n".to_owned(), "?SYNTAX ERROR IN 20".to_owned())
+        );
+        assert_eq!(
+            run_to_error("10 READ A\n20 DATA \"1\""),
+            (String::new(), "?SYNTAX ERROR IN 20".to_owned())
+        );
+        assert_eq!(
+            run_to_error("10 READ A$:PRINT A$;\"!\":READ B$\n20 DATA \"AB\"  ,\"AB\"CD"),
+            ("AB!\n".to_owned(), "?SYNTAX ERROR IN 20".to_owned())
+        );
+        // numbers keep to the range of a floating point variable
+        assert_eq!(
+            run_to_error("10 READ A\n20 DATA 1E99"),
+            (String::new(), "?OVERFLOW ERROR IN 10".to_owned())
+        );
+        assert_eq!(
+            run_to_error("10 READ A%\n20 DATA 40000"),
+            (String::new(), "?ILLEGAL QUANTITY ERROR IN 10".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let source = "10 A$ = \"HELLO\":B$(2) = \", \"\n20 A = 1:A(2) = 2\n30 PRINT A$ + B$(2) + \"WORLD\";A;A(2);C$;\"!\"";
+        assert_eq!(run(source), "HELLO, WORLD12!\n");
+        assert_eq!(
+            run("10 A$ = \"ABC\":PRINT A$ = \"ABC\";A$ < \"ABD\";A$ > \"AB\";\"B\" <= A$;A$ <> \"\""),
+            "11101\n"
+        );
+        assert_eq!(
+            run_to_error("10 A$ = 1"),
+            (String::new(), "?TYPE MISMATCH ERROR IN 10".to_owned())
+        );
+        assert_eq!(
+            run_to_error("10 A = \"X\" + 1"),
+            (String::new(), "?TYPE MISMATCH ERROR IN 10".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_integers() {
+        let source = "10 A% = 3.9:B%(1) = -3.9:A = 1.5:A$ = \"A\"\n20 PRINT A%;\" \";B%(1);\" \";A;\" \";A$;\" \";C%";
+        assert_eq!(run(source), "3 -3 1.5 A 0\n");
+        assert_eq!(run("10 A% = -32767:PRINT A% - 1"), "-32768\n");
+        assert_eq!(
+            run_to_error("10 A% = 32767.5:PRINT A%:A% = -32768"),
+            (
+                "32767\n".to_owned(),
+                "?ILLEGAL QUANTITY ERROR IN 10".to_owned()
+            )
+        );
+        assert_eq!(
+            run_to_error("10 A%(2) = 40000"),
+            (String::new(), "?ILLEGAL QUANTITY ERROR IN 10".to_owned())
+        );
+        assert_eq!(
+            run_to_error("10 A% = \"1\""),
+            (String::new(), "?TYPE MISMATCH ERROR IN 10".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_two_character_names() {
+        let source =
+            "10 SU = 1:SUM = SUM + 1:AB(1) = 3:ABC$ = \"X\":COUNT% = 4\n20 PRINT SU;AB(1);ABD$;CO%";
+        assert_eq!(run(source), "23X4\n");
+        // OR is found inside SCORE, which cannot be a name
+        assert_eq!(
+            run_to_error("10 SCORE = 1"),
+            (String::new(), "?SYNTAX ERROR IN 10".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_strict_names() {
+        let source = "10 SUM = 1:SU = 2:SUM = 3:SU(1) = SU\n20 PRINT SUM:AB$ = \"\":ABC$ = AB$";
+        #[allow(clippy::expect_used)]
+        let program = lpi_parser::parse(&lpi_lexer::lex(source)).expect("Error parsing program");
+        let mut interpreter = Interpreter::new(&program);
+        interpreter.set_strict(true);
+        let mut output = Vec::new();
+        #[allow(clippy::expect_used)]
+        interpreter
+            .interpret(&mut output)
+            .expect("Error interpreting program");
+        assert_eq!(output, b"3\n");
+        assert_eq!(
+            interpreter.warnings(),
+            [
+                "SUM and SU are the same variable in line 10",
+                "AB$ and ABC$ are the same variable in line 20"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_too_long() {
+        // 8 doublings of 1 character reach 256
+        let source = "10 A$ = \"X\"\n20 FOR I = 1 TO 8:PRINT I;:A$ = A$ + A$:NEXT";
+        assert_eq!(
+            run_to_error(source),
+            (
+                "12345678".to_owned(),
+                "?STRING TOO LONG ERROR IN 20".to_owned()
+            )
+        );
     }
-    Ok(())
 }
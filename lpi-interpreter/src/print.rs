@@ -0,0 +1,72 @@
+//! PRINT statement logic
+
+use crate::{error::ErrorKind, interpreter::Interpreter};
+use lpi_parser::ast::PrintItem;
+use std::io::Write;
+
+/// Columns in each of the tab zones that `,` moves between
+const ZONE_WIDTH: usize = 16;
+
+/// From this column on, `,` moves to the start of the next line instead of the next zone
+const LAST_ZONE_START: usize = 24;
+
+#[allow(clippy::module_name_repetitions)]
+pub fn print_statement<T: Write>(
+    interpreter: &mut Interpreter,
+    output: &mut T,
+    items: &[PrintItem],
+) -> Result<(), ErrorKind> {
+    // a trailing separator, TAB or SPC keeps the cursor on the same line
+    let mut newline = true;
+    for item in items {
+        newline = matches!(item, PrintItem::Expr(_));
+        match item {
+            PrintItem::Expr(expr) => {
+                let value = interpreter.evaluate(expr)?.to_string();
+                write_text(interpreter, output, &value)?;
+            }
+            PrintItem::Semicolon => {}
+            PrintItem::Comma => {
+                let column = interpreter.column();
+                if column >= LAST_ZONE_START {
+                    write_text(interpreter, output, "\n")?;
+                } else {
+                    write_spaces(interpreter, output, ZONE_WIDTH - column % ZONE_WIDTH)?;
+                }
+            }
+            PrintItem::Tab(expr) => {
+                // columns count from 1 here; a column the cursor is already past is ignored
+                let target = usize::from(interpreter.evaluate_byte(expr)?);
+                let count = target.saturating_sub(interpreter.column() + 1);
+                write_spaces(interpreter, output, count)?;
+            }
+            PrintItem::Spc(expr) => {
+                let count = usize::from(interpreter.evaluate_byte(expr)?);
+                write_spaces(interpreter, output, count)?;
+            }
+        }
+    }
+    if newline {
+        write_text(interpreter, output, "\n")?;
+    }
+    Ok(())
+}
+
+/// Print text, keeping track of the cursor
+fn write_text<T: Write>(
+    interpreter: &mut Interpreter,
+    output: &mut T,
+    text: &str,
+) -> Result<(), ErrorKind> {
+    output.write_all(text.as_bytes())?;
+    interpreter.track_output(text);
+    Ok(())
+}
+
+fn write_spaces<T: Write>(
+    interpreter: &mut Interpreter,
+    output: &mut T,
+    count: usize,
+) -> Result<(), ErrorKind> {
+    write_text(interpreter, output, &" ".repeat(count))
+}
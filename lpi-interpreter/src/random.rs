@@ -0,0 +1,58 @@
+//! The random number generator behind RND
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A generator of random numbers from 0 up to but not including 1
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+    /// The number most recently given
+    last: f64,
+}
+
+impl Random {
+    /// Make a generator that gives the same numbers for the same seed
+    pub fn new(seed: u64) -> Self {
+        let mut random = Self {
+            state: seed,
+            last: 0.0,
+        };
+        let _first = random.next();
+        random
+    }
+
+    /// Make a generator with a seed that is different every time
+    pub fn unseeded() -> Self {
+        Self::new(RandomState::new().build_hasher().finish())
+    }
+
+    /// Get a number the way `RND` does: a positive argument gives the next number, 0 gives the
+    /// last number again, and a negative argument reseeds the generator from the argument, so
+    /// the numbers after the same negative argument are always the same
+    pub fn rnd(&mut self, argument: f64) -> f64 {
+        if argument == 0.0 {
+            return self.last;
+        }
+        if argument < 0.0 {
+            self.state = argument.to_bits();
+        }
+        self.next()
+    }
+
+    /// Step the generator, with the SplitMix64 algorithm
+    fn next(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut bits = self.state;
+        bits = (bits ^ (bits >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        bits = (bits ^ (bits >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        bits ^= bits >> 31;
+        // the top 53 bits fill the mantissa of a number below 1
+        #[allow(clippy::cast_precision_loss)]
+        let number = (bits >> 11) as f64 / (1_u64 << 53) as f64;
+        self.last = number;
+        number
+    }
+}
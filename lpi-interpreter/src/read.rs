@@ -0,0 +1,62 @@
+//! READ and RESTORE statement logic
+
+use crate::{
+    error::ErrorKind,
+    interpreter::Interpreter,
+    value::{checked_number, read_number, Value},
+};
+use lpi_lexer::token::IdentifierKind;
+use lpi_parser::ast::Variable;
+
+#[allow(clippy::module_name_repetitions)]
+pub fn read_statement(
+    interpreter: &mut Interpreter,
+    variables: &[Variable],
+) -> Result<(), ErrorKind> {
+    for variable in variables {
+        let (item, line, span) = interpreter.next_data_item()?;
+        let Some(value) = data_value(&item, variable.kind) else {
+            // like the ROM, an item that does not fit is reported against its DATA line
+            interpreter.report_error_in(line, span);
+            return Err(ErrorKind::Syntax);
+        };
+        let value = match value {
+            Value::Number(number) => Value::Number(checked_number(number)?),
+            string @ Value::String(_) => string,
+        };
+        interpreter.assign(variable, value)?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub fn restore_statement(interpreter: &mut Interpreter) -> Result<(), ErrorKind> {
+    interpreter.restore();
+    Ok(())
+}
+
+/// Get the value of a DATA item for a variable of the given type, or `None` if the item is not
+/// a number when one is needed or has text after its closing quote. Leading blanks are
+/// skipped; a string item may be quoted, so that it can hold commas and colons, and an empty
+/// item is 0 or the empty string.
+fn data_value(item: &str, kind: IdentifierKind) -> Option<Value> {
+    let item = item.trim_start_matches(' ');
+    if kind == IdentifierKind::String {
+        let string = match item.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((_, rest)) if !rest.trim_start_matches(' ').is_empty() => return None,
+                Some((string, _)) => string,
+                None => quoted,
+            },
+            None => item,
+        };
+        return Some(Value::String(string.to_owned()));
+    }
+    if item.starts_with('"') {
+        return None;
+    }
+    match read_number(item) {
+        (number, false) => Some(Value::Number(number)),
+        (_, true) => None,
+    }
+}
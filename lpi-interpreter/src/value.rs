@@ -0,0 +1,133 @@
+//! Values that expressions evaluate to
+
+use crate::error::ErrorKind;
+use std::iter::Peekable;
+
+/// The most characters a string can hold; a longer one raises `?STRING TOO LONG ERROR`
+pub const MAX_STRING_LENGTH: usize = 255;
+
+/// The largest number Applesoft can hold, about 1.7E+38
+const MAX_NUMBER: f64 = 1.701_411_834_604_692_3e38;
+
+/// Value of an expression
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    /// Number value
+    Number(f64),
+    /// String value
+    String(String),
+}
+
+impl Value {
+    /// Get the value for a truth value, which Applesoft represents as 1 or 0
+    pub const fn from_bool(value: bool) -> Self {
+        Self::Number(if value { 1.0 } else { 0.0 })
+    }
+}
+
+/// Check the result of a calculation: one too big for Applesoft raises `?OVERFLOW ERROR`, and
+/// one that is not a number, such as the root of a negative number, raises
+/// `?ILLEGAL QUANTITY ERROR`
+pub fn checked_number(number: f64) -> Result<f64, ErrorKind> {
+    if number.is_nan() {
+        Err(ErrorKind::IllegalQuantity)
+    } else if number.abs() > MAX_NUMBER {
+        Err(ErrorKind::Overflow)
+    } else {
+        Ok(number)
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => write!(fmt, "{}", format_number(*number)),
+            Self::String(string) => write!(fmt, "{string}"),
+        }
+    }
+}
+
+/// Write a number the way Applesoft prints it: rounded to 9 significant digits, with no
+/// leading zero before the decimal point, and in exponent form such as `1E+10` outside the
+/// range 0.01 to 999999999
+pub fn format_number(number: f64) -> String {
+    if number == 0.0 {
+        return "0".to_owned();
+    }
+    let sign = if number < 0.0 { "-" } else { "" };
+    // the standard exponent form already rounds to the significant digits
+    let scientific = format!("{:.8e}", number.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let digits = mantissa.replace('.', "");
+    let digits = digits.trim_end_matches('0');
+    let text = match usize::try_from(exponent) {
+        // at least 1 but below 1E+09
+        Ok(point) if point < 9 => {
+            if digits.len() > point + 1 {
+                format!("{}.{}", &digits[..=point], &digits[point + 1..])
+            } else {
+                format!("{digits:0<width$}", width = point + 1)
+            }
+        }
+        // at least .01 but below 1
+        Err(_) if exponent >= -2 => {
+            format!(
+                ".{}{digits}",
+                "0".repeat(exponent.unsigned_abs() as usize - 1)
+            )
+        }
+        _ => {
+            let (first, rest) = digits.split_at(1);
+            let point = if rest.is_empty() { "" } else { "." };
+            let exponent_sign = if exponent < 0 { '-' } else { '+' };
+            format!(
+                "{first}{point}{rest}E{exponent_sign}{:02}",
+                exponent.unsigned_abs()
+            )
+        }
+    };
+    format!("{sign}{text}")
+}
+
+/// Read the number at the start of a string the way Applesoft reads a number typed in: blanks
+/// are skipped, and reading stops at the first character that cannot continue the number, so
+/// `"12AB"` gives 12 and a string without a number gives 0. Also returns whether anything but
+/// blanks follows the number.
+pub fn read_number(string: &str) -> (f64, bool) {
+    let mut chars = string.chars().filter(|&ch| ch != ' ').peekable();
+    let mut number = String::new();
+    take_sign(&mut chars, &mut number);
+    let mut mantissa = take_digits(&mut chars, &mut number);
+    if chars.next_if_eq(&'.').is_some() {
+        number.push('.');
+        mantissa |= take_digits(&mut chars, &mut number);
+    }
+    if !mantissa {
+        return (0.0, chars.peek().is_some());
+    }
+    if chars.next_if_eq(&'E').is_some() {
+        let mut exponent = String::from("e");
+        take_sign(&mut chars, &mut exponent);
+        if take_digits(&mut chars, &mut exponent) {
+            number.push_str(&exponent);
+        }
+    }
+    (number.parse().unwrap_or(0.0), chars.peek().is_some())
+}
+
+/// Move a leading `+` or `-` onto the end of the text
+fn take_sign(chars: &mut Peekable<impl Iterator<Item = char>>, text: &mut String) {
+    if let Some(sign) = chars.next_if(|&ch| ch == '+' || ch == '-') {
+        text.push(sign);
+    }
+}
+
+/// Move the leading digits onto the end of the text; returns whether there were any
+fn take_digits(chars: &mut Peekable<impl Iterator<Item = char>>, text: &mut String) -> bool {
+    let start = text.len();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        text.push(digit);
+    }
+    text.len() > start
+}
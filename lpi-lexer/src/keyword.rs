@@ -0,0 +1,429 @@
+//! Reserved words
+
+/// Applesoft reserved word
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Keyword {
+    /// END
+    End,
+    /// FOR
+    For,
+    /// NEXT
+    Next,
+    /// DATA
+    Data,
+    /// INPUT
+    Input,
+    /// DEL
+    Del,
+    /// DIM
+    Dim,
+    /// READ
+    Read,
+    /// GR
+    Gr,
+    /// TEXT
+    Text,
+    /// PR#
+    PrNumber,
+    /// IN#
+    InNumber,
+    /// CALL
+    Call,
+    /// PLOT
+    Plot,
+    /// HLIN
+    Hlin,
+    /// VLIN
+    Vlin,
+    /// HGR2
+    Hgr2,
+    /// HGR
+    Hgr,
+    /// HCOLOR=
+    Hcolor,
+    /// HPLOT
+    Hplot,
+    /// DRAW
+    Draw,
+    /// XDRAW
+    Xdraw,
+    /// HTAB
+    Htab,
+    /// HOME
+    Home,
+    /// ROT=
+    Rot,
+    /// SCALE=
+    Scale,
+    /// SHLOAD
+    Shload,
+    /// TRACE
+    Trace,
+    /// NOTRACE
+    Notrace,
+    /// NORMAL
+    Normal,
+    /// INVERSE
+    Inverse,
+    /// FLASH
+    Flash,
+    /// COLOR=
+    Color,
+    /// POP
+    Pop,
+    /// VTAB
+    Vtab,
+    /// HIMEM:
+    Himem,
+    /// LOMEM:
+    Lomem,
+    /// ONERR
+    Onerr,
+    /// RESUME
+    Resume,
+    /// RECALL
+    Recall,
+    /// STORE
+    Store,
+    /// SPEED=
+    Speed,
+    /// LET
+    Let,
+    /// GOTO
+    Goto,
+    /// RUN
+    Run,
+    /// IF
+    If,
+    /// RESTORE
+    Restore,
+    /// &
+    Ampersand,
+    /// GOSUB
+    Gosub,
+    /// RETURN
+    Return,
+    /// REM
+    Rem,
+    /// STOP
+    Stop,
+    /// ON
+    On,
+    /// WAIT
+    Wait,
+    /// LOAD
+    Load,
+    /// SAVE
+    Save,
+    /// DEF
+    Def,
+    /// POKE
+    Poke,
+    /// PRINT
+    Print,
+    /// CONT
+    Cont,
+    /// LIST
+    List,
+    /// CLEAR
+    Clear,
+    /// GET
+    Get,
+    /// NEW
+    New,
+    /// TAB(
+    Tab,
+    /// TO
+    To,
+    /// FN
+    Fn,
+    /// SPC(
+    Spc,
+    /// THEN
+    Then,
+    /// AT
+    At,
+    /// NOT
+    Not,
+    /// STEP
+    Step,
+    /// AND
+    And,
+    /// OR
+    Or,
+    /// SGN
+    Sgn,
+    /// INT
+    Int,
+    /// ABS
+    Abs,
+    /// USR
+    Usr,
+    /// FRE
+    Fre,
+    /// SCRN(
+    Scrn,
+    /// PDL
+    Pdl,
+    /// POS
+    Pos,
+    /// SQR
+    Sqr,
+    /// RND
+    Rnd,
+    /// LOG
+    Log,
+    /// EXP
+    Exp,
+    /// COS
+    Cos,
+    /// SIN
+    Sin,
+    /// TAN
+    Tan,
+    /// ATN
+    Atn,
+    /// PEEK
+    Peek,
+    /// LEN
+    Len,
+    /// STR$
+    Str,
+    /// VAL
+    Val,
+    /// ASC
+    Asc,
+    /// CHR$
+    Chr,
+    /// LEFT$
+    Left,
+    /// RIGHT$
+    Right,
+    /// MID$
+    Mid,
+}
+
+impl Keyword {
+    /// All reserved words, in the order of the ROM token table
+    pub const ALL: &'static [Self] = &[
+        Self::End,
+        Self::For,
+        Self::Next,
+        Self::Data,
+        Self::Input,
+        Self::Del,
+        Self::Dim,
+        Self::Read,
+        Self::Gr,
+        Self::Text,
+        Self::PrNumber,
+        Self::InNumber,
+        Self::Call,
+        Self::Plot,
+        Self::Hlin,
+        Self::Vlin,
+        Self::Hgr2,
+        Self::Hgr,
+        Self::Hcolor,
+        Self::Hplot,
+        Self::Draw,
+        Self::Xdraw,
+        Self::Htab,
+        Self::Home,
+        Self::Rot,
+        Self::Scale,
+        Self::Shload,
+        Self::Trace,
+        Self::Notrace,
+        Self::Normal,
+        Self::Inverse,
+        Self::Flash,
+        Self::Color,
+        Self::Pop,
+        Self::Vtab,
+        Self::Himem,
+        Self::Lomem,
+        Self::Onerr,
+        Self::Resume,
+        Self::Recall,
+        Self::Store,
+        Self::Speed,
+        Self::Let,
+        Self::Goto,
+        Self::Run,
+        Self::If,
+        Self::Restore,
+        Self::Ampersand,
+        Self::Gosub,
+        Self::Return,
+        Self::Rem,
+        Self::Stop,
+        Self::On,
+        Self::Wait,
+        Self::Load,
+        Self::Save,
+        Self::Def,
+        Self::Poke,
+        Self::Print,
+        Self::Cont,
+        Self::List,
+        Self::Clear,
+        Self::Get,
+        Self::New,
+        Self::Tab,
+        Self::To,
+        Self::Fn,
+        Self::Spc,
+        Self::Then,
+        Self::At,
+        Self::Not,
+        Self::Step,
+        Self::And,
+        Self::Or,
+        Self::Sgn,
+        Self::Int,
+        Self::Abs,
+        Self::Usr,
+        Self::Fre,
+        Self::Scrn,
+        Self::Pdl,
+        Self::Pos,
+        Self::Sqr,
+        Self::Rnd,
+        Self::Log,
+        Self::Exp,
+        Self::Cos,
+        Self::Sin,
+        Self::Tan,
+        Self::Atn,
+        Self::Peek,
+        Self::Len,
+        Self::Str,
+        Self::Val,
+        Self::Asc,
+        Self::Chr,
+        Self::Left,
+        Self::Right,
+        Self::Mid,
+    ];
+
+    /// Get the spelling of the reserved word as it appears in the ROM token table
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub const fn spelling(self) -> &'static str {
+        match self {
+            Self::End => "END",
+            Self::For => "FOR",
+            Self::Next => "NEXT",
+            Self::Data => "DATA",
+            Self::Input => "INPUT",
+            Self::Del => "DEL",
+            Self::Dim => "DIM",
+            Self::Read => "READ",
+            Self::Gr => "GR",
+            Self::Text => "TEXT",
+            Self::PrNumber => "PR#",
+            Self::InNumber => "IN#",
+            Self::Call => "CALL",
+            Self::Plot => "PLOT",
+            Self::Hlin => "HLIN",
+            Self::Vlin => "VLIN",
+            Self::Hgr2 => "HGR2",
+            Self::Hgr => "HGR",
+            Self::Hcolor => "HCOLOR=",
+            Self::Hplot => "HPLOT",
+            Self::Draw => "DRAW",
+            Self::Xdraw => "XDRAW",
+            Self::Htab => "HTAB",
+            Self::Home => "HOME",
+            Self::Rot => "ROT=",
+            Self::Scale => "SCALE=",
+            Self::Shload => "SHLOAD",
+            Self::Trace => "TRACE",
+            Self::Notrace => "NOTRACE",
+            Self::Normal => "NORMAL",
+            Self::Inverse => "INVERSE",
+            Self::Flash => "FLASH",
+            Self::Color => "COLOR=",
+            Self::Pop => "POP",
+            Self::Vtab => "VTAB",
+            Self::Himem => "HIMEM:",
+            Self::Lomem => "LOMEM:",
+            Self::Onerr => "ONERR",
+            Self::Resume => "RESUME",
+            Self::Recall => "RECALL",
+            Self::Store => "STORE",
+            Self::Speed => "SPEED=",
+            Self::Let => "LET",
+            Self::Goto => "GOTO",
+            Self::Run => "RUN",
+            Self::If => "IF",
+            Self::Restore => "RESTORE",
+            Self::Ampersand => "&",
+            Self::Gosub => "GOSUB",
+            Self::Return => "RETURN",
+            Self::Rem => "REM",
+            Self::Stop => "STOP",
+            Self::On => "ON",
+            Self::Wait => "WAIT",
+            Self::Load => "LOAD",
+            Self::Save => "SAVE",
+            Self::Def => "DEF",
+            Self::Poke => "POKE",
+            Self::Print => "PRINT",
+            Self::Cont => "CONT",
+            Self::List => "LIST",
+            Self::Clear => "CLEAR",
+            Self::Get => "GET",
+            Self::New => "NEW",
+            Self::Tab => "TAB(",
+            Self::To => "TO",
+            Self::Fn => "FN",
+            Self::Spc => "SPC(",
+            Self::Then => "THEN",
+            Self::At => "AT",
+            Self::Not => "NOT",
+            Self::Step => "STEP",
+            Self::And => "AND",
+            Self::Or => "OR",
+            Self::Sgn => "SGN",
+            Self::Int => "INT",
+            Self::Abs => "ABS",
+            Self::Usr => "USR",
+            Self::Fre => "FRE",
+            Self::Scrn => "SCRN(",
+            Self::Pdl => "PDL",
+            Self::Pos => "POS",
+            Self::Sqr => "SQR",
+            Self::Rnd => "RND",
+            Self::Log => "LOG",
+            Self::Exp => "EXP",
+            Self::Cos => "COS",
+            Self::Sin => "SIN",
+            Self::Tan => "TAN",
+            Self::Atn => "ATN",
+            Self::Peek => "PEEK",
+            Self::Len => "LEN",
+            Self::Str => "STR$",
+            Self::Val => "VAL",
+            Self::Asc => "ASC",
+            Self::Chr => "CHR$",
+            Self::Left => "LEFT$",
+            Self::Right => "RIGHT$",
+            Self::Mid => "MID$",
+        }
+    }
+
+    /// Get the text of the keyword token; a trailing `(` or `=` in the spelling is lexed as its
+    /// own token
+    #[must_use]
+    pub fn text(self) -> &'static str {
+        self.spelling().trim_end_matches(['(', '='])
+    }
+}
+
+impl std::fmt::Display for Keyword {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.text())
+    }
+}
@@ -2,97 +2,304 @@
 //!
 //! The lexer takes the input source code and converts it into tokens.
 
-#[derive(Debug, PartialEq)]
-enum ParseState {
-    Start,
-    Number,
-    Identifier,
-    String,
+use keyword::Keyword;
+use span::{Span, Spanned};
+use token::{IdentifierKind, Operator, Token};
+
+pub mod keyword;
+pub mod span;
+pub mod token;
+
+/// Walks the source text one character at a time
+struct Lexer<'a> {
+    input: &'a str,
+    position: usize,
+    line: usize,
+    line_start: usize,
+    /// Whether a number here is a line number, as at the start of a line or after `GOTO`
+    line_number_next: bool,
+    /// Whether the tokens since the last `GOTO`, `GOSUB` or `THEN` are all line numbers and
+    /// commas, as in `ON X GOTO 10, 20`
+    in_targets: bool,
 }
 
-/// Stub function to return a string
-#[must_use]
-pub fn lex(input: &str) -> Vec<String> {
-    // we want to split the input into tokens
-    let mut tokens = Vec::new();
-    let mut state = ParseState::Start;
-    let mut current_token = String::new();
-
-    for token in input.chars() {
-        match token {
-            '"' => {
-                if state == ParseState::String {
-                    current_token.push(token);
-                    tokens.push(current_token.clone());
-                    current_token.clear();
-                    state = ParseState::Start;
-                } else {
-                    state = ParseState::String;
-                    current_token.push(token);
+impl<'a> Lexer<'a> {
+    const fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            position: 0,
+            line: 1,
+            line_start: 0,
+            line_number_next: true,
+            in_targets: false,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn char_at(&self, offset: usize) -> Option<char> {
+        self.input[offset..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.position += ch.len_utf8();
+        if ch == '\n' {
+            self.line += 1;
+            self.line_start = self.position;
+        }
+        Some(ch)
+    }
+
+    /// Consume characters while the predicate holds and return them
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            let _unused = self.bump();
+        }
+        &self.input[start..self.position]
+    }
+
+    /// Consume characters up to the given byte offset
+    fn advance_to(&mut self, offset: usize) {
+        while self.position < offset {
+            let _unused = self.bump();
+        }
+    }
+
+    /// Get the byte offset of the first non-blank character at or after the given offset
+    fn skip_blanks(&self, offset: usize) -> usize {
+        offset + self.input[offset..].len()
+            - self.input[offset..].trim_start_matches(is_blank).len()
+    }
+
+    fn next_token(&mut self) -> Option<Spanned<Token>> {
+        let _unused = self.take_while(is_blank);
+        let ch = self.peek()?;
+        let start = self.position;
+        let line = self.line;
+        let column = self.input[self.line_start..start].chars().count() + 1;
+        let token = if let Some((keyword, end)) = self.match_keyword(start) {
+            self.advance_to(end);
+            self.keyword(keyword)
+        } else {
+            match ch {
+                '"' => self.string(),
+                '0'..='9' if self.line_number_next => self.line_number(),
+                '0'..='9' | '.' => self.number(),
+                'A'..='Z' | 'a'..='z' => self.identifier(),
+                _ => {
+                    let _unused = self.bump();
+                    match ch {
+                        '\n' => Token::Newline,
+                        ':' => Token::Colon,
+                        ';' => Token::Semicolon,
+                        ',' => Token::Comma,
+                        '(' => Token::LeftParen,
+                        ')' => Token::RightParen,
+                        // the ROM tokenizes `?` as `PRINT`, so it lists back as `PRINT`
+                        '?' => Token::Keyword(Keyword::Print),
+                        _ => Operator::from_char(ch).map_or(Token::Unknown(ch), Token::Operator),
+                    }
                 }
             }
-            _ if state == ParseState::String => {
-                current_token.push(token);
-            }
-            ' ' => {
-                if state == ParseState::String {
-                    current_token.push(token);
-                } else if !current_token.is_empty() {
-                    tokens.push(current_token.clone());
-                    current_token.clear();
-                    state = ParseState::Start;
-                }
+        };
+        self.in_targets = match token {
+            Token::Keyword(Keyword::Goto | Keyword::Gosub | Keyword::Then) => true,
+            Token::Number(_) | Token::Comma => self.in_targets,
+            _ => false,
+        };
+        self.line_number_next =
+            token == Token::Newline || (self.in_targets && !matches!(token, Token::Number(_)));
+        let span = Span {
+            start,
+            end: self.position,
+            line,
+            column,
+        };
+        Some(Spanned { value: token, span })
+    }
+
+    /// Find the reserved word starting at the given offset the way the ROM tokenizer does: the
+    /// entries are tried in table order and the first one that matches wins, ignoring any blanks
+    /// inside the word, so `SCORE` holds `OR` and `G O T O` is `GOTO`.
+    /// Returns the keyword and the offset just past it.
+    fn match_keyword(&self, offset: usize) -> Option<(Keyword, usize)> {
+        Keyword::ALL.iter().copied().find_map(|keyword| {
+            let end = self.match_spelling(offset, keyword.text())?;
+            let tail = &keyword.spelling()[keyword.text().len()..];
+            let _tail_end = self.match_spelling(end, tail)?;
+            // `AT` followed by `N` is `ATN`, and followed by `O` it is `A` then `TO`
+            if keyword == Keyword::At
+                && matches!(
+                    self.char_at(self.skip_blanks(end)),
+                    Some('N' | 'n' | 'O' | 'o')
+                )
+            {
+                return None;
             }
-            'E' | 'e' if state == ParseState::Number => {
-                current_token.push(token);
+            Some((keyword, end))
+        })
+    }
+
+    /// Match the text at the given offset against a spelling, ignoring case and blanks
+    fn match_spelling(&self, offset: usize, spelling: &str) -> Option<usize> {
+        let mut position = offset;
+        for expected in spelling.chars() {
+            position = self.skip_blanks(position);
+            let actual = self.char_at(position)?;
+            if !actual.eq_ignore_ascii_case(&expected) {
+                return None;
             }
-            '+' | '-' if state == ParseState::Number => {
-                let last_token = current_token.chars().last().unwrap_or(' ');
-                if last_token == 'E' || last_token == 'e' {
-                    current_token.push(token);
-                } else {
-                    tokens.push(current_token.clone());
-                    current_token.clear();
-                    current_token.push(token);
-                    state = ParseState::Start;
+            position += actual.len_utf8();
+        }
+        Some(position)
+    }
+
+    /// Lex a string literal; the closing quote may be left off at the end of a line
+    fn string(&mut self) -> Token {
+        let _quote = self.bump();
+        let text = self.take_while(|ch| ch != '"' && ch != '\n');
+        if self.peek() == Some('"') {
+            let _quote = self.bump();
+        }
+        Token::String(text.to_owned())
+    }
+
+    /// Consume digits, and one decimal point if allowed, skipping any blanks between them
+    fn digits(&mut self, text: &mut String, mut allow_point: bool) {
+        loop {
+            let next = self.skip_blanks(self.position);
+            match self.char_at(next) {
+                Some(ch) if ch.is_ascii_digit() || (allow_point && ch == '.') => {
+                    self.advance_to(next);
+                    let _unused = self.bump();
+                    text.push(ch);
+                    allow_point &= ch != '.';
                 }
+                _ => break,
             }
-            '0'..='9' | 'A'..='Z' | 'a'..='z' if state == ParseState::Identifier => {
-                current_token.push(token);
-            }
-            'A'..='Z' | 'a'..='z' => {
-                state = ParseState::Identifier;
-                current_token.push(token);
+        }
+    }
+
+    /// Lex a line number, which is only digits, so that `10E1=5` is line 10 setting `E1`
+    fn line_number(&mut self) -> Token {
+        let mut text = String::new();
+        self.digits(&mut text, false);
+        Token::Number(text)
+    }
+
+    /// Lex a number literal, with an optional exponent such as `1.5E-10`; a lone decimal point
+    /// is read as 0, as the ROM does
+    fn number(&mut self) -> Token {
+        let mut text = String::new();
+        self.digits(&mut text, true);
+        if text == "." {
+            text = "0".to_owned();
+        }
+
+        let exponent = self.skip_blanks(self.position);
+        if matches!(self.char_at(exponent), Some('E' | 'e')) {
+            let mut digits_start = self.skip_blanks(exponent + 1);
+            let sign = self
+                .char_at(digits_start)
+                .filter(|ch| *ch == '+' || *ch == '-');
+            if sign.is_some() {
+                digits_start = self.skip_blanks(digits_start + 1);
             }
-            '$' | '%' if state == ParseState::Identifier => {
-                current_token.push(token);
-                tokens.push(current_token.clone());
-                current_token.clear();
-                state = ParseState::Start;
+            if self
+                .char_at(digits_start)
+                .is_some_and(|ch| ch.is_ascii_digit())
+            {
+                text.push('E');
+                text.extend(sign);
+                self.advance_to(digits_start);
+                self.digits(&mut text, false);
             }
-            '0'..='9' | '.' => {
-                if state != ParseState::Number {
-                    state = ParseState::Number;
-                    tokens.push(current_token.clone());
-                    current_token.clear();
+        }
+        Token::Number(text)
+    }
+
+    /// Lex a variable name with its optional `$` or `%` suffix; the name ends where a reserved
+    /// word begins, and blanks inside it are ignored
+    fn identifier(&mut self) -> Token {
+        let mut name = String::new();
+        loop {
+            let next = self.skip_blanks(self.position);
+            match self.char_at(next) {
+                Some(ch)
+                    if (ch.is_ascii_alphabetic() || (ch.is_ascii_digit() && !name.is_empty()))
+                        && self.match_keyword(next).is_none() =>
+                {
+                    self.advance_to(next);
+                    let _unused = self.bump();
+                    name.push(ch);
                 }
-                current_token.push(token);
+                _ => break,
             }
-            _ => {
-                tokens.push(current_token.clone());
-                current_token.clear();
-                state = ParseState::Start;
-                current_token.push(token);
+        }
+
+        let suffix = self.skip_blanks(self.position);
+        let kind = match self.char_at(suffix) {
+            Some('$') => IdentifierKind::String,
+            Some('%') => IdentifierKind::Integer,
+            _ => IdentifierKind::Float,
+        };
+        if kind != IdentifierKind::Float {
+            self.advance_to(suffix + 1);
+        }
+        Token::Identifier(name, kind)
+    }
+
+    /// Lex the raw text of a DATA statement, up to a colon outside quotes or the end of the line
+    fn data(&mut self) -> Token {
+        let start = self.position;
+        let mut quoted = false;
+        while let Some(ch) = self.peek() {
+            match ch {
+                '\n' | '\r' => break,
+                ':' if !quoted => break,
+                '"' => quoted = !quoted,
+                _ => {}
             }
+            let _unused = self.bump();
         }
+        Token::Data(self.input[start..self.position].to_owned())
     }
-    if !current_token.is_empty() {
-        tokens.push(current_token);
+
+    fn keyword(&mut self, keyword: Keyword) -> Token {
+        match keyword {
+            Keyword::Rem => {
+                let text = self.take_while(|ch| ch != '\n' && ch != '\r');
+                Token::Rem(text.to_owned())
+            }
+            Keyword::Data => self.data(),
+            _ => Token::Keyword(keyword),
+        }
     }
+}
 
-    // remove empty tokens
-    tokens.retain(|token| !token.is_empty());
+const fn is_blank(ch: char) -> bool {
+    matches!(ch, ' ' | '\t' | '\r')
+}
 
+/// Split the source code into tokens, each with the span of source text it came from.
+///
+/// Like the Applesoft ROM, blanks are only significant inside strings, REM and DATA, so
+/// crunched lines such as `10FORI=1TO10:PRINTI:NEXT` are read the same as spaced-out ones.
+#[must_use]
+pub fn lex(input: &str) -> Vec<Spanned<Token>> {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token() {
+        tokens.push(token);
+    }
     tokens
 }
 
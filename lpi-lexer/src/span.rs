@@ -0,0 +1,46 @@
+//! Source locations
+
+/// Location of a piece of source text
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset just past the last character
+    pub end: usize,
+    /// Line of the first character, starting at 1
+    pub line: usize,
+    /// Column of the first character, starting at 1
+    pub column: usize,
+}
+
+impl Span {
+    /// Get a span covering both this span and a later one
+    #[must_use]
+    pub const fn to(self, other: Self) -> Self {
+        Self {
+            end: other.end,
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// A value together with the location it came from
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Spanned<T> {
+    /// The value
+    pub value: T,
+    /// Where the value came from
+    pub span: Span,
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for Spanned<T> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(fmt)
+    }
+}
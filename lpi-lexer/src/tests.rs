@@ -1,57 +1,345 @@
 use super::*;
 
+fn tokens(input: &str) -> Vec<Token> {
+    lex(input).into_iter().map(|token| token.value).collect()
+}
+
+fn number(value: &str) -> Token {
+    Token::Number(value.to_owned())
+}
+
+fn float_identifier(name: &str) -> Token {
+    Token::Identifier(name.to_owned(), IdentifierKind::Float)
+}
+
 #[test]
 fn test_print_statement() {
     let input = "10 PRINT \"HELLO, WORLD!\"";
-    let expected = vec!["10", "PRINT", "\"HELLO, WORLD!\""];
-    assert_eq!(lex(input), expected);
+    let expected = vec![
+        number("10"),
+        Token::Keyword(Keyword::Print),
+        Token::String("HELLO, WORLD!".to_owned()),
+    ];
+    assert_eq!(tokens(input), expected);
 }
 
 #[test]
 fn test_identifiers() {
     let input = "LET A = 10";
-    let expected = vec!["LET", "A", "=", "10"];
-    assert_eq!(lex(input), expected);
+    let expected = vec![
+        Token::Keyword(Keyword::Let),
+        float_identifier("A"),
+        Token::Operator(Operator::Equal),
+        number("10"),
+    ];
+    assert_eq!(tokens(input), expected);
 }
 
 #[test]
 fn test_array_indexing() {
     let input = "AL(0) = 10";
-    let expected = vec!["AL", "(", "0", ")", "=", "10"];
-    assert_eq!(lex(input), expected);
+    let expected = vec![
+        float_identifier("AL"),
+        Token::LeftParen,
+        number("0"),
+        Token::RightParen,
+        Token::Operator(Operator::Equal),
+        number("10"),
+    ];
+    assert_eq!(tokens(input), expected);
 }
 
 #[test]
 fn test_mathematical_expression() {
     let input = "A = 10 + 20";
-    let expected = vec!["A", "=", "10", "+", "20"];
-    assert_eq!(lex(input), expected);
+    let expected = vec![
+        float_identifier("A"),
+        Token::Operator(Operator::Equal),
+        number("10"),
+        Token::Operator(Operator::Plus),
+        number("20"),
+    ];
+    assert_eq!(tokens(input), expected);
 }
 
 #[test]
 fn test_float_number() {
     let input = "A = 10.5";
-    let expected = vec!["A", "=", "10.5"];
-    assert_eq!(lex(input), expected);
+    let expected = vec![
+        float_identifier("A"),
+        Token::Operator(Operator::Equal),
+        number("10.5"),
+    ];
+    assert_eq!(tokens(input), expected);
 }
 
 #[test]
 fn test_exponential_number() {
     let input = "A = 1.5E-10";
-    let expected = vec!["A", "=", "1.5E-10"];
-    assert_eq!(lex(input), expected);
+    let expected = vec![
+        float_identifier("A"),
+        Token::Operator(Operator::Equal),
+        number("1.5E-10"),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_decimal_points() {
+    // a number takes only one decimal point, and a lone point is 0
+    let input = "PRINT 1.2.3;.";
+    let expected = vec![
+        Token::Keyword(Keyword::Print),
+        number("1.2"),
+        number(".3"),
+        Token::Semicolon,
+        number("0"),
+    ];
+    assert_eq!(tokens(input), expected);
 }
 
 #[test]
 fn test_identifier_with_suffix() {
     let input = "A$ = 10";
-    let expected = vec!["A$", "=", "10"];
-    assert_eq!(lex(input), expected);
+    let expected = vec![
+        Token::Identifier("A".to_owned(), IdentifierKind::String),
+        Token::Operator(Operator::Equal),
+        number("10"),
+    ];
+    assert_eq!(tokens(input), expected);
 }
 
 #[test]
 fn test_bad_identifier() {
     let input = "A$B = 10";
-    let expected = vec!["A$", "B", "=", "10"];
-    assert_eq!(lex(input), expected);
+    let expected = vec![
+        Token::Identifier("A".to_owned(), IdentifierKind::String),
+        float_identifier("B"),
+        Token::Operator(Operator::Equal),
+        number("10"),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_string_functions_and_integers() {
+    let input = "I% = LEN(LEFT$(A$, 2))";
+    let expected = vec![
+        Token::Identifier("I".to_owned(), IdentifierKind::Integer),
+        Token::Operator(Operator::Equal),
+        Token::Keyword(Keyword::Len),
+        Token::LeftParen,
+        Token::Keyword(Keyword::Left),
+        Token::LeftParen,
+        Token::Identifier("A".to_owned(), IdentifierKind::String),
+        Token::Comma,
+        number("2"),
+        Token::RightParen,
+        Token::RightParen,
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_colons_and_newlines() {
+    let input = "10 A = 1: B = 2\n20 END";
+    let expected = vec![
+        number("10"),
+        float_identifier("A"),
+        Token::Operator(Operator::Equal),
+        number("1"),
+        Token::Colon,
+        float_identifier("B"),
+        Token::Operator(Operator::Equal),
+        number("2"),
+        Token::Newline,
+        number("20"),
+        Token::Keyword(Keyword::End),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_rem_keeps_rest_of_line() {
+    let input = "10 REM IT'S: \"ALL\" TEXT\n20 PR#6";
+    let expected = vec![
+        number("10"),
+        Token::Rem(" IT'S: \"ALL\" TEXT".to_owned()),
+        Token::Newline,
+        number("20"),
+        Token::Keyword(Keyword::PrNumber),
+        number("6"),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_spans() {
+    let input = "10 A = 1\n20 PRINT \"HI\"";
+    let spans: Vec<Span> = lex(input).into_iter().map(|token| token.span).collect();
+    let span = |start, end, line, column| Span {
+        start,
+        end,
+        line,
+        column,
+    };
+    let expected = vec![
+        span(0, 2, 1, 1),
+        span(3, 4, 1, 4),
+        span(5, 6, 1, 6),
+        span(7, 8, 1, 8),
+        span(8, 9, 1, 9),
+        span(9, 11, 2, 1),
+        span(12, 17, 2, 4),
+        span(18, 22, 2, 10),
+    ];
+    assert_eq!(spans, expected);
+}
+
+#[test]
+fn test_crunched_line() {
+    let input = "10FORI=1TO10:PRINTI:NEXT";
+    let expected = vec![
+        number("10"),
+        Token::Keyword(Keyword::For),
+        float_identifier("I"),
+        Token::Operator(Operator::Equal),
+        number("1"),
+        Token::Keyword(Keyword::To),
+        number("10"),
+        Token::Colon,
+        Token::Keyword(Keyword::Print),
+        float_identifier("I"),
+        Token::Colon,
+        Token::Keyword(Keyword::Next),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_reserved_words_inside_names() {
+    // the tokenizer finds OR inside SCORE, and blanks inside words and numbers are ignored
+    let input = "SCORE = G O TO 1 000";
+    let expected = vec![
+        float_identifier("SC"),
+        Token::Keyword(Keyword::Or),
+        float_identifier("E"),
+        Token::Operator(Operator::Equal),
+        Token::Keyword(Keyword::Goto),
+        number("1000"),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_at_quirks() {
+    // ATN is ATN, ATO is A TO, and AT on its own is AT
+    let input = "X=ATN(1):FORI=ATON:HLIN1,2AT3";
+    let expected = vec![
+        float_identifier("X"),
+        Token::Operator(Operator::Equal),
+        Token::Keyword(Keyword::Atn),
+        Token::LeftParen,
+        number("1"),
+        Token::RightParen,
+        Token::Colon,
+        Token::Keyword(Keyword::For),
+        float_identifier("I"),
+        Token::Operator(Operator::Equal),
+        float_identifier("A"),
+        Token::Keyword(Keyword::To),
+        float_identifier("N"),
+        Token::Colon,
+        Token::Keyword(Keyword::Hlin),
+        number("1"),
+        Token::Comma,
+        number("2"),
+        Token::Keyword(Keyword::At),
+        number("3"),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_punctuation_in_reserved_words() {
+    // TAB( only matches with its parenthesis, which is lexed on its own
+    let input = "PRINTTAB(5);TABLE:HCOLOR=3:HIMEM:8192";
+    let expected = vec![
+        Token::Keyword(Keyword::Print),
+        Token::Keyword(Keyword::Tab),
+        Token::LeftParen,
+        number("5"),
+        Token::RightParen,
+        Token::Semicolon,
+        float_identifier("TABLE"),
+        Token::Colon,
+        Token::Keyword(Keyword::Hcolor),
+        Token::Operator(Operator::Equal),
+        number("3"),
+        Token::Colon,
+        Token::Keyword(Keyword::Himem),
+        number("8192"),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_data_is_not_crunched() {
+    let input = "10 DATA ATONE, \"A:B\", SCORE:PRINT";
+    let expected = vec![
+        number("10"),
+        Token::Data(" ATONE, \"A:B\", SCORE".to_owned()),
+        Token::Colon,
+        Token::Keyword(Keyword::Print),
+    ];
+    assert_eq!(tokens(input), expected);
+}
+
+#[test]
+fn test_question_mark_is_print() {
+    let expected = vec![
+        number("10"),
+        Token::Keyword(Keyword::Print),
+        float_identifier("A"),
+        Token::Colon,
+        Token::Keyword(Keyword::Print),
+        Token::String("?".to_owned()),
+    ];
+    assert_eq!(tokens("10 ?A:? \"?\""), expected);
+}
+
+#[test]
+fn test_line_numbers_are_digits() {
+    // a line number takes no exponent, so `E1` is a variable, with or without the blank
+    let assign = [
+        float_identifier("E1"),
+        Token::Operator(Operator::Equal),
+        number("5"),
+    ];
+    for input in ["10 E1=5", "10E1=5"] {
+        let mut expected = vec![number("10")];
+        expected.extend(assign.clone());
+        assert_eq!(tokens(input), expected);
+    }
+    // so are the targets of GOTO, GOSUB and THEN, but not other numbers
+    let input = "ON 1E1 GOTO 10,2 0E1:IF 1 THEN 3E1\n4E1";
+    let expected = vec![
+        Token::Keyword(Keyword::On),
+        number("1E1"),
+        Token::Keyword(Keyword::Goto),
+        number("10"),
+        Token::Comma,
+        number("20"),
+        float_identifier("E1"),
+        Token::Colon,
+        Token::Keyword(Keyword::If),
+        number("1"),
+        Token::Keyword(Keyword::Then),
+        number("3"),
+        float_identifier("E1"),
+        Token::Newline,
+        number("4"),
+        float_identifier("E1"),
+    ];
+    assert_eq!(tokens(input), expected);
 }
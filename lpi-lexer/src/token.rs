@@ -0,0 +1,134 @@
+//! Tokens produced by the lexer
+
+use crate::keyword::Keyword;
+
+/// Lexical token
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Token {
+    /// Reserved word
+    Keyword(Keyword),
+    /// Number literal, as written in the source
+    Number(String),
+    /// String literal, without the surrounding quotes
+    String(String),
+    /// Variable name, without its type suffix
+    Identifier(String, IdentifierKind),
+    /// Arithmetic or relational operator
+    Operator(Operator),
+    /// `(`
+    LeftParen,
+    /// `)`
+    RightParen,
+    /// `,`
+    Comma,
+    /// `;`
+    Semicolon,
+    /// `:` statement separator
+    Colon,
+    /// End of a line
+    Newline,
+    /// Remark text following REM
+    Rem(String),
+    /// Raw text following DATA, up to the next statement
+    Data(String),
+    /// Any other character
+    Unknown(char),
+}
+
+/// Variable type, given by the suffix of the name
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IdentifierKind {
+    /// No suffix
+    Float,
+    /// `%` suffix
+    Integer,
+    /// `$` suffix
+    String,
+}
+
+impl IdentifierKind {
+    /// Get the suffix for this kind of variable
+    #[must_use]
+    pub const fn suffix(self) -> &'static str {
+        match self {
+            Self::Float => "",
+            Self::Integer => "%",
+            Self::String => "$",
+        }
+    }
+}
+
+/// Arithmetic or relational operator
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Operator {
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Multiply,
+    /// `/`
+    Divide,
+    /// `^`
+    Power,
+    /// `=`
+    Equal,
+    /// `<`
+    Less,
+    /// `>`
+    Greater,
+}
+
+impl Operator {
+    /// Get the operator for a character
+    #[must_use]
+    pub const fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            '+' => Some(Self::Plus),
+            '-' => Some(Self::Minus),
+            '*' => Some(Self::Multiply),
+            '/' => Some(Self::Divide),
+            '^' => Some(Self::Power),
+            '=' => Some(Self::Equal),
+            '<' => Some(Self::Less),
+            '>' => Some(Self::Greater),
+            _ => None,
+        }
+    }
+
+    /// Get the character for the operator
+    #[must_use]
+    pub const fn as_char(self) -> char {
+        match self {
+            Self::Plus => '+',
+            Self::Minus => '-',
+            Self::Multiply => '*',
+            Self::Divide => '/',
+            Self::Power => '^',
+            Self::Equal => '=',
+            Self::Less => '<',
+            Self::Greater => '>',
+        }
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Keyword(keyword) => write!(fmt, "{keyword}"),
+            Self::Number(number) => write!(fmt, "{number}"),
+            Self::String(string) => write!(fmt, "\"{string}\""),
+            Self::Identifier(name, kind) => write!(fmt, "{name}{}", kind.suffix()),
+            Self::Operator(operator) => write!(fmt, "{}", operator.as_char()),
+            Self::LeftParen => write!(fmt, "("),
+            Self::RightParen => write!(fmt, ")"),
+            Self::Comma => write!(fmt, ","),
+            Self::Semicolon => write!(fmt, ";"),
+            Self::Colon => write!(fmt, ":"),
+            Self::Newline => write!(fmt, "end of line"),
+            Self::Rem(text) => write!(fmt, "REM{text}"),
+            Self::Data(text) => write!(fmt, "DATA{text}"),
+            Self::Unknown(ch) => write!(fmt, "{ch}"),
+        }
+    }
+}
@@ -0,0 +1,550 @@
+//! Typed syntax tree
+//!
+//! Every type here can be displayed, which lists it back as Applesoft source text.
+
+use crate::error::ParseError;
+use lpi_lexer::{
+    span::{Span, Spanned},
+    token::{IdentifierKind, Token},
+};
+use std::ops::RangeInclusive;
+
+/// A whole program
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Program {
+    /// The lines of the program, in the order they appear in the source
+    pub lines: Vec<Line>,
+}
+
+/// A line of the program
+#[derive(Debug, PartialEq, Clone)]
+pub struct Line {
+    /// The line number, or `None` for a line without one
+    pub number: Option<u16>,
+    /// The colon separated statements on the line, in order
+    pub statements: Vec<Spanned<Statement>>,
+    /// The source text of the line
+    pub span: Span,
+}
+
+/// A statement
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    /// `[LET] variable = value`
+    Let {
+        /// The variable to assign to
+        variable: Variable,
+        /// The value to assign
+        value: Spanned<Expr>,
+    },
+    /// `PRINT` followed by the items to print
+    Print(Vec<PrintItem>),
+    /// `END`
+    End,
+    /// `DEF FN name(parameter) = body`, which defines a function of one number
+    Def {
+        /// The name of the function
+        name: Variable,
+        /// The variable that holds the argument while the body is evaluated
+        parameter: Variable,
+        /// The expression the function gives
+        body: Spanned<Expr>,
+    },
+    /// `DIM` followed by the arrays to create, each with the largest subscript of each of its
+    /// dimensions
+    Dim(Vec<Variable>),
+    /// `FOR variable = start TO end [STEP step]`
+    For {
+        /// The loop variable
+        variable: Variable,
+        /// The first value of the loop variable
+        start: Spanned<Expr>,
+        /// The value the loop runs up to, or down to for a negative step
+        end: Spanned<Expr>,
+        /// The amount added to the loop variable on each `NEXT`, 1 if not given
+        step: Option<Spanned<Expr>>,
+    },
+    /// `NEXT` followed by the loop variables to step, innermost first; with none, the
+    /// innermost loop is stepped
+    Next(Vec<Variable>),
+    /// `IF condition THEN`, which guards the statements after it on the line; `IF ... THEN n`
+    /// and `IF ... GOTO n` are followed by a `GOTO`
+    If(Spanned<Expr>),
+    /// `GOTO` a line
+    Goto(u16),
+    /// `ON index GOTO` one of the lines, counting from 1
+    OnGoto {
+        /// Which line to go to
+        index: Spanned<Expr>,
+        /// The lines to choose from
+        targets: Vec<u16>,
+    },
+    /// `GOSUB` a subroutine at a line
+    Gosub(u16),
+    /// `ON index GOSUB` one of the lines, counting from 1
+    OnGosub {
+        /// Which line to call
+        index: Spanned<Expr>,
+        /// The lines to choose from
+        targets: Vec<u16>,
+    },
+    /// `RETURN` from the newest subroutine
+    Return,
+    /// `POP` the newest subroutine return, so the next `RETURN` goes to the one before it
+    Pop,
+    /// `REM` followed by the remark text
+    Rem(String),
+    /// `DATA` followed by its items, as written between the commas, with any blanks and quotes
+    Data(Vec<String>),
+    /// `READ` followed by the variables to read DATA items into
+    Read(Vec<Variable>),
+    /// `RESTORE`, which reads DATA items from the start of the program again
+    Restore,
+    /// The rest of a line that holds a syntax error, which is only reported if it is run
+    Error {
+        /// The first syntax error in the statement
+        error: ParseError,
+        /// The tokens from the start of the statement to the end of the line
+        tokens: Vec<Token>,
+    },
+}
+
+/// An item in a PRINT statement
+#[derive(Debug, PartialEq, Clone)]
+pub enum PrintItem {
+    /// A value to print
+    Expr(Spanned<Expr>),
+    /// `;`, which prints nothing
+    Semicolon,
+    /// `,`, which moves to the next tab zone
+    Comma,
+    /// `TAB(column)`, which moves right to a column, counting from 1
+    Tab(Spanned<Expr>),
+    /// `SPC(count)`, which prints spaces
+    Spc(Spanned<Expr>),
+}
+
+/// A variable, or an element of an array when it has subscripts
+#[derive(Debug, PartialEq, Clone)]
+pub struct Variable {
+    /// The name, without its type suffix
+    pub name: String,
+    /// The type of the variable
+    pub kind: IdentifierKind,
+    /// The subscripts of an array element
+    pub subscripts: Vec<Spanned<Expr>>,
+    /// The source text of the variable, with its subscripts
+    pub span: Span,
+}
+
+/// An expression; the expressions inside it keep their own spans
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    /// A number literal
+    Number(f64),
+    /// A string literal
+    String(String),
+    /// A variable or array element
+    Variable(Variable),
+    /// A unary operation
+    Unary(UnaryOp, Box<Spanned<Self>>),
+    /// A binary operation
+    Binary(BinaryOp, Box<Spanned<Self>>, Box<Spanned<Self>>),
+    /// A call of a built-in function
+    Function(Function, Vec<Spanned<Self>>),
+    /// `FN name(argument)`, a call of a function defined with `DEF FN`
+    UserFunction(Variable, Box<Spanned<Self>>),
+}
+
+/// A built-in function
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Function {
+    /// `ABS`, the absolute value
+    Abs,
+    /// `SGN`, the sign as -1, 0 or 1
+    Sgn,
+    /// `INT`, the largest whole number not above the argument
+    Int,
+    /// `SQR`, the square root
+    Sqr,
+    /// `EXP`, e to the power of the argument
+    Exp,
+    /// `LOG`, the natural logarithm
+    Log,
+    /// `SIN`, the sine of an angle in radians
+    Sin,
+    /// `COS`, the cosine of an angle in radians
+    Cos,
+    /// `TAN`, the tangent of an angle in radians
+    Tan,
+    /// `ATN`, the arctangent in radians
+    Atn,
+    /// `RND`, a random number from 0 up to 1
+    Rnd,
+    /// `LEN`, the number of characters in a string
+    Len,
+    /// `LEFT$(string, length)`, the start of a string
+    Left,
+    /// `RIGHT$(string, length)`, the end of a string
+    Right,
+    /// `MID$(string, start[, length])`, part of a string, counting from 1; without a length,
+    /// the rest of the string
+    Mid,
+    /// `STR$`, a number as `PRINT` would print it
+    Str,
+    /// `VAL`, the number at the start of a string
+    Val,
+    /// `CHR$`, the character with an ASCII code
+    Chr,
+    /// `ASC`, the ASCII code of the first character of a string
+    Asc,
+}
+
+impl Function {
+    /// Get the smallest and largest number of arguments the function takes
+    #[must_use]
+    pub const fn arity(self) -> RangeInclusive<usize> {
+        match self {
+            Self::Left | Self::Right => 2..=2,
+            Self::Mid => 2..=3,
+            _ => 1..=1,
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Abs => "ABS",
+            Self::Sgn => "SGN",
+            Self::Int => "INT",
+            Self::Sqr => "SQR",
+            Self::Exp => "EXP",
+            Self::Log => "LOG",
+            Self::Sin => "SIN",
+            Self::Cos => "COS",
+            Self::Tan => "TAN",
+            Self::Atn => "ATN",
+            Self::Rnd => "RND",
+            Self::Len => "LEN",
+            Self::Left => "LEFT$",
+            Self::Right => "RIGHT$",
+            Self::Mid => "MID$",
+            Self::Str => "STR$",
+            Self::Val => "VAL",
+            Self::Chr => "CHR$",
+            Self::Asc => "ASC",
+        }
+    }
+}
+
+/// A unary operator
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UnaryOp {
+    /// `-`
+    Negate,
+    /// `+`
+    Plus,
+    /// `NOT`
+    Not,
+}
+
+/// A binary operator
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BinaryOp {
+    /// `+`
+    Add,
+    /// `-`
+    Subtract,
+    /// `*`
+    Multiply,
+    /// `/`
+    Divide,
+    /// `^`
+    Power,
+    /// `=`
+    Equal,
+    /// `<>`
+    NotEqual,
+    /// `<`
+    Less,
+    /// `>`
+    Greater,
+    /// `<=`
+    LessEqual,
+    /// `>=`
+    GreaterEqual,
+    /// `AND`
+    And,
+    /// `OR`
+    Or,
+}
+
+impl BinaryOp {
+    /// Get the binding power of the operator; higher binds tighter.
+    /// All binary operators are left associative.
+    #[must_use]
+    pub const fn binding_power(self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Equal
+            | Self::NotEqual
+            | Self::Less
+            | Self::Greater
+            | Self::LessEqual
+            | Self::GreaterEqual => 3,
+            Self::Add | Self::Subtract => 4,
+            Self::Multiply | Self::Divide => 5,
+            Self::Power => 7,
+        }
+    }
+
+    const fn symbol(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Power => "^",
+            Self::Equal => "=",
+            Self::NotEqual => "<>",
+            Self::Less => "<",
+            Self::Greater => ">",
+            Self::LessEqual => "<=",
+            Self::GreaterEqual => ">=",
+            Self::And => " AND ",
+            Self::Or => " OR ",
+        }
+    }
+}
+
+impl UnaryOp {
+    /// Binding power of the unary operators, which bind tighter than `*` but looser than `^`
+    pub const BINDING_POWER: u8 = 6;
+
+    const fn symbol(self) -> &'static str {
+        match self {
+            Self::Negate => "-",
+            Self::Plus => "+",
+            Self::Not => "NOT ",
+        }
+    }
+}
+
+impl std::fmt::Display for UnaryOp {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.symbol())
+    }
+}
+
+impl std::fmt::Display for Function {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+impl std::fmt::Display for BinaryOp {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.symbol())
+    }
+}
+
+impl std::fmt::Display for Program {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(fmt, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Line {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(number) = self.number {
+            write!(fmt, "{number} ")?;
+        }
+        let mut previous: Option<&Statement> = None;
+        for statement in &self.statements {
+            match previous {
+                Some(Statement::If(_)) => write!(fmt, " ")?,
+                Some(_) => write!(fmt, ":")?,
+                None => {}
+            }
+            write!(fmt, "{}", statement.value)?;
+            previous = Some(&statement.value);
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Statement {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Let { variable, value } => write!(fmt, "{variable} = {value}"),
+            Self::Print(items) => {
+                write!(fmt, "PRINT ")?;
+                for item in items {
+                    match item {
+                        PrintItem::Expr(expr) => write!(fmt, "{expr}")?,
+                        PrintItem::Semicolon => write!(fmt, ";")?,
+                        PrintItem::Comma => write!(fmt, ",")?,
+                        PrintItem::Tab(expr) => write!(fmt, "TAB({expr})")?,
+                        PrintItem::Spc(expr) => write!(fmt, "SPC({expr})")?,
+                    }
+                }
+                Ok(())
+            }
+            Self::End => write!(fmt, "END"),
+            Self::Def {
+                name,
+                parameter,
+                body,
+            } => write!(fmt, "DEF FN {name}({parameter}) = {body}"),
+            Self::Dim(arrays) => {
+                write!(fmt, "DIM ")?;
+                for (index, array) in arrays.iter().enumerate() {
+                    if index > 0 {
+                        write!(fmt, ",")?;
+                    }
+                    write!(fmt, "{array}")?;
+                }
+                Ok(())
+            }
+            Self::For {
+                variable,
+                start,
+                end,
+                step,
+            } => {
+                write!(fmt, "FOR {variable} = {start} TO {end}")?;
+                if let Some(step) = step {
+                    write!(fmt, " STEP {step}")?;
+                }
+                Ok(())
+            }
+            Self::Next(variables) => {
+                write!(fmt, "NEXT")?;
+                for (index, variable) in variables.iter().enumerate() {
+                    write!(fmt, "{}{variable}", if index == 0 { " " } else { "," })?;
+                }
+                Ok(())
+            }
+            Self::If(condition) => write!(fmt, "IF {condition} THEN"),
+            Self::Goto(target) => write!(fmt, "GOTO {target}"),
+            Self::OnGoto { index, targets } => {
+                write!(fmt, "ON {index} GOTO ")?;
+                write_targets(fmt, targets)
+            }
+            Self::Gosub(target) => write!(fmt, "GOSUB {target}"),
+            Self::OnGosub { index, targets } => {
+                write!(fmt, "ON {index} GOSUB ")?;
+                write_targets(fmt, targets)
+            }
+            Self::Return => write!(fmt, "RETURN"),
+            Self::Pop => write!(fmt, "POP"),
+            Self::Rem(text) => write!(fmt, "REM{text}"),
+            Self::Data(items) => write!(fmt, "DATA{}", items.join(",")),
+            Self::Read(variables) => {
+                write!(fmt, "READ ")?;
+                for (index, variable) in variables.iter().enumerate() {
+                    if index > 0 {
+                        write!(fmt, ",")?;
+                    }
+                    write!(fmt, "{variable}")?;
+                }
+                Ok(())
+            }
+            Self::Restore => write!(fmt, "RESTORE"),
+            Self::Error { tokens, .. } => {
+                for token in tokens {
+                    match token {
+                        Token::Keyword(keyword) => write!(fmt, " {keyword} ")?,
+                        Token::Colon => write!(fmt, ":")?,
+                        token => write!(fmt, "{token}")?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Write a list of line numbers separated by commas
+fn write_targets(fmt: &mut std::fmt::Formatter<'_>, targets: &[u16]) -> std::fmt::Result {
+    for (index, target) in targets.iter().enumerate() {
+        if index > 0 {
+            write!(fmt, ",")?;
+        }
+        write!(fmt, "{target}")?;
+    }
+    Ok(())
+}
+
+impl std::fmt::Display for Variable {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}{}", self.name, self.kind.suffix())?;
+        if !self.subscripts.is_empty() {
+            write!(fmt, "(")?;
+            for (index, subscript) in self.subscripts.iter().enumerate() {
+                if index > 0 {
+                    write!(fmt, ",")?;
+                }
+                write!(fmt, "{subscript}")?;
+            }
+            write!(fmt, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl Expr {
+    /// Get the binding power of the outermost operator, if there is one
+    const fn binding_power(&self) -> Option<u8> {
+        match self {
+            Self::Unary(..) => Some(UnaryOp::BINDING_POWER),
+            Self::Binary(operator, ..) => Some(operator.binding_power()),
+            _ => None,
+        }
+    }
+
+    /// Write the expression, in parentheses if its operator binds looser than `min_power`
+    fn fmt_operand(&self, fmt: &mut std::fmt::Formatter<'_>, min_power: u8) -> std::fmt::Result {
+        if self.binding_power().is_some_and(|power| power < min_power) {
+            write!(fmt, "({self})")
+        } else {
+            write!(fmt, "{self}")
+        }
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => write!(fmt, "{number}"),
+            Self::String(string) => write!(fmt, "\"{string}\""),
+            Self::Variable(variable) => write!(fmt, "{variable}"),
+            Self::Unary(operator, operand) => {
+                write!(fmt, "{operator}")?;
+                operand.value.fmt_operand(fmt, UnaryOp::BINDING_POWER)
+            }
+            Self::Binary(operator, left, right) => {
+                let power = operator.binding_power();
+                left.value.fmt_operand(fmt, power)?;
+                write!(fmt, "{operator}")?;
+                // the operators are left associative, so an equal operator on the right needs
+                // parentheses
+                right.value.fmt_operand(fmt, power + 1)
+            }
+            Self::Function(function, arguments) => {
+                write!(fmt, "{function}(")?;
+                for (index, argument) in arguments.iter().enumerate() {
+                    if index > 0 {
+                        write!(fmt, ",")?;
+                    }
+                    write!(fmt, "{argument}")?;
+                }
+                write!(fmt, ")")
+            }
+            Self::UserFunction(name, argument) => write!(fmt, "FN {name}({argument})"),
+        }
+    }
+}
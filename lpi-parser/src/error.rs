@@ -0,0 +1,40 @@
+//! Syntax errors found while parsing
+
+use lpi_lexer::{span::Span, token::Token};
+
+/// A syntax error, with where it was found and what the parser wanted instead
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    /// The number of the line holding the error, or `None` for a line without one
+    pub line: Option<u16>,
+    /// Where the unexpected token is in the source
+    pub span: Span,
+    /// What the parser expected at that point, such as `expression` or `)`
+    pub expected: String,
+    /// The token found instead; the end of the source is reported as the end of a line
+    pub found: Token,
+}
+
+impl ParseError {
+    /// Get the 1-based column of the unexpected token
+    #[must_use]
+    pub const fn column(&self) -> usize {
+        self.span.column
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(fmt, "in line {line} ")?;
+        }
+        write!(
+            fmt,
+            "at {}: expected {}, found {}",
+            self.span, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
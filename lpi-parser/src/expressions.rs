@@ -1,91 +1,182 @@
-use crate::{node_type::NodeType, parse_node::ParseNode};
+use crate::{
+    ast::{BinaryOp, Expr, Function, UnaryOp},
+    parsing::{ParseResult, Parser},
+};
+use lpi_lexer::{
+    keyword::Keyword,
+    span::Spanned,
+    token::{Operator, Token},
+};
 
-pub fn combine(node: &mut ParseNode) {
-    let mut did_something = true;
-    while did_something {
-        did_something = false;
-        did_something |= combine_arithmetic_expressions(node, &["*", "/"]);
-        did_something |= combine_arithmetic_expressions(node, &["+", "-"]);
-        did_something |= combine_arithmetic_expressions(node, &["^"]);
-        did_something |= combine_parens(node);
+/// How deeply operands may nest, such as in parentheses; the ROM runs out of stack after 36
+/// levels of parentheses
+const MAX_NESTING: usize = 36;
+
+impl Parser<'_> {
+    /// Parse an expression
+    pub fn expression(&mut self) -> ParseResult<Spanned<Expr>> {
+        self.expression_with_power(0)
     }
-}
 
-fn combine_parens(node: &mut ParseNode) -> bool {
-    // here we want to combine a left paren, a numerical, and a right paren
-    let mut did_something = false;
-    let mut continue_parsing = true;
-    while continue_parsing {
-        continue_parsing = false;
-        for i in 0..node.children.len() - 1 {
-            if node.children[i].node_type == NodeType::Symbol
-                && node.children[i].value == "("
-                && is_numerical(node.children[i + 1].node_type)
-                && node.children[i + 2].node_type == NodeType::Symbol
-                && node.children[i + 2].value == ")"
-            {
-                let left = node.children.remove(i);
-                let num = node.children.remove(i);
-                let right = node.children.remove(i);
-                // combine the expression into a new node with these children
-                let new_node = ParseNode {
-                    node_type: NodeType::Expression,
-                    value: String::new(),
-                    children: vec![left, num, right],
-                };
-                // replace the combined nodes with the new one
-                node.children.insert(i, new_node);
-                continue_parsing = true;
-                did_something = true;
+    /// Parse an expression whose operators all bind at least as tightly as `min_power`
+    fn expression_with_power(&mut self, min_power: u8) -> ParseResult<Spanned<Expr>> {
+        let start = self.position;
+        let mut left = self.primary()?;
+        while let Some(operator) = self.peek_binary_operator() {
+            let power = operator.binding_power();
+            if power < min_power {
                 break;
             }
+            let operator = self.binary_operator(operator);
+            let right = self.expression_with_power(power + 1)?;
+            left = self.spanned(
+                start,
+                Expr::Binary(operator, Box::new(left), Box::new(right)),
+            );
         }
+        Ok(left)
     }
-    did_something
-}
 
-#[allow(clippy::collapsible_if)]
-fn combine_arithmetic_expressions(node: &mut ParseNode, operators: &[&str]) -> bool {
-    // combine arithmetic expressions
-    let mut did_something = false;
-    let mut continue_parsing = true;
-    while continue_parsing {
-        continue_parsing = false;
-        for i in 0..node.children.len() - 1 {
-            if is_arithmetic_operator(&node.children[i], operators) {
-                if i > 0 && i < node.children.len() - 1 {
-                    if is_numerical(node.children[i - 1].node_type)
-                        && is_numerical(node.children[i + 1].node_type)
-                    {
-                        let left = node.children.remove(i - 1);
-                        let op = node.children.remove(i - 1);
-                        let right = node.children.remove(i - 1);
-                        // combine the expression into a new node with these children
-                        let new_node = ParseNode {
-                            node_type: NodeType::Expression,
-                            value: String::new(),
-                            children: vec![left, op, right],
-                        };
-                        // replace the combined nodes with the new one
-                        node.children.insert(i - 1, new_node);
-                        continue_parsing = true;
-                        did_something = true;
-                        break;
-                    }
-                }
+    /// Get the operator the next token starts, without taking it
+    fn peek_binary_operator(&self) -> Option<BinaryOp> {
+        let operator = match self.peek()? {
+            Token::Operator(Operator::Plus) => BinaryOp::Add,
+            Token::Operator(Operator::Minus) => BinaryOp::Subtract,
+            Token::Operator(Operator::Multiply) => BinaryOp::Multiply,
+            Token::Operator(Operator::Divide) => BinaryOp::Divide,
+            Token::Operator(Operator::Power) => BinaryOp::Power,
+            Token::Operator(Operator::Equal) => BinaryOp::Equal,
+            Token::Operator(Operator::Less) => BinaryOp::Less,
+            Token::Operator(Operator::Greater) => BinaryOp::Greater,
+            Token::Keyword(Keyword::And) => BinaryOp::And,
+            Token::Keyword(Keyword::Or) => BinaryOp::Or,
+            _ => return None,
+        };
+        Some(operator)
+    }
+
+    /// Take the operator that was just peeked, joining a relational operator spelled with two
+    /// symbols such as `<=`, or its alternate spelling `=<`, into one
+    fn binary_operator(&mut self, operator: BinaryOp) -> BinaryOp {
+        self.advance();
+        let joined = match (operator, self.peek_binary_operator()) {
+            (BinaryOp::Less, Some(BinaryOp::Equal)) | (BinaryOp::Equal, Some(BinaryOp::Less)) => {
+                BinaryOp::LessEqual
             }
+            (BinaryOp::Greater, Some(BinaryOp::Equal))
+            | (BinaryOp::Equal, Some(BinaryOp::Greater)) => BinaryOp::GreaterEqual,
+            (BinaryOp::Less, Some(BinaryOp::Greater))
+            | (BinaryOp::Greater, Some(BinaryOp::Less)) => BinaryOp::NotEqual,
+            _ => return operator,
+        };
+        self.advance();
+        joined
+    }
+
+    /// Parse an operand, failing rather than recursing without bound when operands nest too
+    /// deeply
+    fn primary(&mut self) -> ParseResult<Spanned<Expr>> {
+        if self.depth > MAX_NESTING {
+            return Err(self.error("fewer nested expressions"));
         }
+        let start = self.position;
+        self.depth += 1;
+        let expr = self.operand();
+        self.depth -= 1;
+        Ok(self.spanned(start, expr?))
     }
-    did_something
-}
 
-fn is_numerical(node_type: NodeType) -> bool {
-    node_type == NodeType::Number
-        || node_type == NodeType::Float
-        || node_type == NodeType::Identifier
-        || node_type == NodeType::Expression
+    /// Parse a number, string, variable, unary operation or parenthesized expression
+    fn operand(&mut self) -> ParseResult<Expr> {
+        let expr = match self.peek() {
+            Some(Token::Operator(Operator::Minus)) => self.unary(UnaryOp::Negate)?,
+            Some(Token::Operator(Operator::Plus)) => self.unary(UnaryOp::Plus)?,
+            Some(Token::Keyword(Keyword::Not)) => self.unary(UnaryOp::Not)?,
+            Some(Token::Number(number)) => {
+                let number = number.parse().map_err(|_| self.error("number"))?;
+                self.advance();
+                Expr::Number(number)
+            }
+            Some(Token::String(string)) => {
+                self.advance();
+                Expr::String(string.clone())
+            }
+            Some(Token::Identifier(..)) => Expr::Variable(self.variable()?),
+            Some(Token::Keyword(Keyword::Fn)) => self.user_function()?,
+            Some(Token::Keyword(keyword)) => match function(*keyword) {
+                Some(function) => self.function_call(function)?,
+                None => return Err(self.error("expression")),
+            },
+            Some(Token::LeftParen) => {
+                self.advance();
+                let inner = self.expression()?;
+                self.expect(&Token::RightParen)?;
+                inner.value
+            }
+            _ => return Err(self.error("expression")),
+        };
+        Ok(expr)
+    }
+
+    /// Take the name of a built-in function and parse its arguments
+    fn function_call(&mut self, function: Function) -> ParseResult<Expr> {
+        self.advance();
+        self.expect(&Token::LeftParen)?;
+        let arity = function.arity();
+        let mut arguments = vec![self.expression()?];
+        while arguments.len() < *arity.end() {
+            if arguments.len() < *arity.start() {
+                self.expect(&Token::Comma)?;
+            } else if !self.eat(&Token::Comma) {
+                break;
+            }
+            arguments.push(self.expression()?);
+        }
+        self.expect(&Token::RightParen)?;
+        Ok(Expr::Function(function, arguments))
+    }
+
+    /// Take `FN` and parse the name of the function and its argument
+    fn user_function(&mut self) -> ParseResult<Expr> {
+        self.advance();
+        let name = self.function_variable()?;
+        self.expect(&Token::LeftParen)?;
+        let argument = self.expression()?;
+        self.expect(&Token::RightParen)?;
+        Ok(Expr::UserFunction(name, Box::new(argument)))
+    }
+
+    /// Take a unary operator and parse its operand
+    fn unary(&mut self, operator: UnaryOp) -> ParseResult<Expr> {
+        self.advance();
+        let operand = self.expression_with_power(UnaryOp::BINDING_POWER)?;
+        Ok(Expr::Unary(operator, Box::new(operand)))
+    }
 }
 
-fn is_arithmetic_operator(node: &ParseNode, operators: &[&str]) -> bool {
-    node.node_type == NodeType::Symbol && operators.contains(&node.value.as_str())
+/// Get the built-in function a keyword names, if it names one
+const fn function(keyword: Keyword) -> Option<Function> {
+    let function = match keyword {
+        Keyword::Abs => Function::Abs,
+        Keyword::Sgn => Function::Sgn,
+        Keyword::Int => Function::Int,
+        Keyword::Sqr => Function::Sqr,
+        Keyword::Exp => Function::Exp,
+        Keyword::Log => Function::Log,
+        Keyword::Sin => Function::Sin,
+        Keyword::Cos => Function::Cos,
+        Keyword::Tan => Function::Tan,
+        Keyword::Atn => Function::Atn,
+        Keyword::Rnd => Function::Rnd,
+        Keyword::Len => Function::Len,
+        Keyword::Left => Function::Left,
+        Keyword::Right => Function::Right,
+        Keyword::Mid => Function::Mid,
+        Keyword::Str => Function::Str,
+        Keyword::Val => Function::Val,
+        Keyword::Chr => Function::Chr,
+        Keyword::Asc => Function::Asc,
+        _ => return None,
+    };
+    Some(function)
 }
@@ -1,93 +1,55 @@
 //! Applesoft II BASIC parser
 //!
-//! The parser takes the output of the lexer and builds a parse tree.
+//! The parser takes the output of the lexer and builds a typed syntax tree.
 
-use node_type::NodeType;
-use parse_node::ParseNode;
+use ast::{Program, Statement};
+use error::ParseError;
+use lpi_lexer::{span::Spanned, token::Token};
+use parsing::Parser;
 
+pub mod ast;
+pub mod error;
 mod expressions;
-pub mod node_type;
-pub mod parse_node;
 mod parsing;
 
-/// Stub function to return a string
-#[must_use]
-pub fn parse(tokens: &[String]) -> ParseNode {
-    // we want to build a parse tree
-    let mut root = ParseNode {
-        node_type: NodeType::Program,
-        value: String::new(),
-        children: Vec::new(),
-    };
-
-    let mut line = ParseNode {
-        node_type: NodeType::Line,
-        value: String::new(),
-        children: Vec::new(),
-    };
-    // first just map each token to its type
-    for token in tokens {
-        if let Some(node) = parsing::parse_string(token) {
-            line.children.push(node);
-            continue;
-        }
-        if line.children.is_empty() {
-            if let Some(node) = parsing::parse_line_number(token) {
-                line.children.push(node);
-                continue;
-            }
-        }
-        if let Some(node) = parsing::parse_numeric(token) {
-            line.children.push(node);
-            continue;
-        }
-        if let Some(node) = parsing::parse_identifier(token) {
-            line.children.push(node);
-            continue;
-        }
-        if token == "\n" {
-            // push the current node as a new line
-            parse_line(&mut line);
-            root.children.push(line);
-            line = ParseNode {
-                node_type: NodeType::Line,
-                value: String::new(),
-                children: Vec::new(),
-            };
-            continue;
-        }
-        // anything else is just a symbol
-        let node = ParseNode {
-            node_type: NodeType::Symbol,
-            value: token.clone(),
-            children: Vec::new(),
-        };
-        line.children.push(node);
-    }
-    if !line.children.is_empty() {
-        parse_line(&mut line);
-        root.children.push(line);
+/// Build a syntax tree from the lexer tokens.
+///
+/// Each line holds its optional line number followed by its statements, which are separated by
+/// colons in the source.
+///
+/// # Errors
+/// Returns every syntax error in the program, in source order; parsing carries on with the next
+/// line after an error, so one bad line does not hide the errors in the lines after it
+pub fn parse(tokens: &[Spanned<Token>]) -> Result<Program, Vec<ParseError>> {
+    let program = parse_with_recovery(tokens);
+    let errors: Vec<ParseError> = program
+        .lines
+        .iter()
+        .flat_map(|line| &line.statements)
+        .filter_map(|statement| match &statement.value {
+            Statement::Error { error, .. } => Some(error.clone()),
+            _ => None,
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
     }
-
-    root
 }
 
-fn parse_line(node: &mut ParseNode) {
-    // combine arithmetic expressions until there are no more to combine
-    expressions::combine(node);
-
-    // if we have a line number directly followed by an identifier, change it into a LET statement
-    if node.children.len() > 1
-        && node.children[0].node_type == NodeType::LineNumber
-        && node.children[1].node_type == NodeType::Identifier
-    {
-        let let_node = ParseNode {
-            node_type: NodeType::StatementName,
-            value: "LET".to_owned(),
-            children: Vec::new(),
-        };
-        node.children.insert(1, let_node);
+/// Build a syntax tree from the lexer tokens, even if some lines hold syntax errors.
+///
+/// A syntax error poisons only the rest of its line, which becomes a [`Statement::Error`]
+/// after the statements before it; Applesoft reports the error only when the line is run.
+#[must_use]
+pub fn parse_with_recovery(tokens: &[Spanned<Token>]) -> Program {
+    let mut parser = Parser::new(tokens);
+    let mut program = Program::default();
+    while let Some(line) = parser.parse_line() {
+        program.lines.push(line);
     }
+    program
 }
 
 #[cfg(test)]
@@ -1,62 +1,519 @@
-use crate::{node_type::NodeType, parse_node::ParseNode};
+use crate::{
+    ast::{Expr, Line, PrintItem, Statement, Variable},
+    error::ParseError,
+};
+use lpi_lexer::{
+    keyword::Keyword,
+    span::{Span, Spanned},
+    token::{IdentifierKind, Operator, Token},
+};
 
-const KEYWORDS: &[&str] = &["PRINT", "LET", "END"];
+/// The largest line number Applesoft accepts
+const MAX_LINE_NUMBER: u16 = 63999;
 
-pub fn parse_string(token: &str) -> Option<ParseNode> {
-    if token.starts_with('"') && token.ends_with('"') {
-        let node = ParseNode {
-            node_type: NodeType::String,
-            value: token.to_owned(),
-            children: Vec::new(),
+/// The most characters the ROM takes in a line typed in
+const MAX_LINE_LENGTH: usize = 239;
+
+/// Walks the lexer tokens of a program
+pub struct Parser<'a> {
+    tokens: &'a [Spanned<Token>],
+    /// Index of the next token to take
+    pub position: usize,
+    /// How many expressions enclose the one being parsed
+    pub depth: usize,
+}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+impl<'a> Parser<'a> {
+    pub const fn new(tokens: &'a [Spanned<Token>]) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    pub fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position).map(|token| &token.value)
+    }
+
+    pub fn next(&mut self) -> Option<&'a Token> {
+        let token = self.peek()?;
+        self.advance();
+        Some(token)
+    }
+
+    /// Move past the next token
+    pub const fn advance(&mut self) {
+        self.position += 1;
+    }
+
+    /// Take the next token if it matches the predicate
+    pub fn next_if(&mut self, predicate: impl FnOnce(&Token) -> bool) -> Option<&'a Token> {
+        if self.peek().is_some_and(predicate) {
+            self.next()
+        } else {
+            None
+        }
+    }
+
+    /// Take the next token if it is the given one
+    pub fn eat(&mut self, expected: &Token) -> bool {
+        self.next_if(|token| token == expected).is_some()
+    }
+
+    /// Check whether the current statement has ended
+    pub fn at_statement_end(&self) -> bool {
+        matches!(self.peek(), None | Some(Token::Colon | Token::Newline))
+    }
+
+    /// Get the span of the token at the given position
+    fn span_at(&self, position: usize) -> Span {
+        self.tokens
+            .get(position)
+            .or_else(|| self.tokens.last())
+            .map(|token| token.span)
+            .unwrap_or_default()
+    }
+
+    /// Get the span from the token at `start` up to the last token taken
+    fn span_from(&self, start: usize) -> Span {
+        let first = self.span_at(start);
+        if self.position > start {
+            first.to(self.span_at(self.position - 1))
+        } else {
+            Span {
+                end: first.start,
+                ..first
+            }
+        }
+    }
+
+    /// Give a value the span from the token at `start` up to the last token taken
+    pub fn spanned<T>(&self, start: usize, value: T) -> Spanned<T> {
+        Spanned {
+            value,
+            span: self.span_from(start),
+        }
+    }
+
+    /// Make an error for an unexpected token at the current position
+    pub fn error(&self, expected: &str) -> ParseError {
+        let span = match self.tokens.get(self.position) {
+            Some(token) => token.span,
+            // the source has run out, so point just past its end
+            None => {
+                let last = self.span_at(self.position);
+                Span {
+                    start: last.end,
+                    column: last.column + (last.end - last.start),
+                    ..last
+                }
+            }
         };
+        ParseError {
+            line: None,
+            span,
+            expected: expected.to_owned(),
+            found: self.peek().cloned().unwrap_or(Token::Newline),
+        }
+    }
 
-        return Some(node);
+    /// Take the given token, or fail if it is not next
+    pub fn expect(&mut self, expected: &Token) -> ParseResult<()> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&expected.to_string()))
+        }
     }
-    None
-}
 
-pub fn parse_numeric(token: &str) -> Option<ParseNode> {
-    if token.chars().any(char::is_numeric) {
-        let node_type = if token.contains('.') {
-            NodeType::Float
+    /// Parse the tokens up to the end of the next line; a syntax error poisons the rest of
+    /// its line, which is kept as an error statement after the statements before it
+    pub fn parse_line(&mut self) -> Option<Line> {
+        while self.eat(&Token::Newline) {}
+        let start = self.position;
+        self.peek()?;
+
+        let number = match self.peek() {
+            Some(Token::Number(number)) if number.chars().all(|ch| ch.is_ascii_digit()) => {
+                match self.line_number() {
+                    Ok(number) => Some(number),
+                    // a number out of range is no line number, so the whole line is bad
+                    Err(error) => return Some(self.bad_line(start, start, None, error)),
+                }
+            }
+            _ => None,
+        };
+        if let Err(error) = self.check_line_length(start) {
+            let statement_start = start + usize::from(number.is_some());
+            return Some(self.bad_line(start, statement_start, number, error));
+        }
+
+        let mut statements = Vec::new();
+        loop {
+            while self.eat(&Token::Colon) {}
+            if matches!(self.peek(), None | Some(Token::Newline)) {
+                break;
+            }
+            let statement_start = self.position;
+            let after_if = matches!(
+                statements.last(),
+                Some(Spanned {
+                    value: Statement::If(_),
+                    ..
+                })
+            );
+            match self.parse_statement(after_if) {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    statements.push(self.skip_line(statement_start, number, error));
+                    break;
+                }
+            }
+        }
+        let span = self.span_from(start);
+        Some(Line {
+            number,
+            statements,
+            span,
+        })
+    }
+
+    /// Make a line whose statements from `statement_start` on are poisoned by a syntax error
+    fn bad_line(
+        &mut self,
+        start: usize,
+        statement_start: usize,
+        number: Option<u16>,
+        error: ParseError,
+    ) -> Line {
+        let statements = vec![self.skip_line(statement_start, number, error)];
+        Line {
+            number,
+            statements,
+            span: self.span_from(start),
+        }
+    }
+
+    /// Check that the line starting at the token at `start` fits in the ROM's input buffer;
+    /// this also bounds how long a chain of operators can be
+    fn check_line_length(&mut self, start: usize) -> ParseResult<()> {
+        let limit = self.span_at(start).start + MAX_LINE_LENGTH;
+        let mut position = start;
+        while let Some(token) = self.tokens.get(position) {
+            if token.value == Token::Newline {
+                break;
+            }
+            if token.span.end > limit {
+                self.position = position;
+                return Err(self.error(&format!("line of at most {MAX_LINE_LENGTH} characters")));
+            }
+            position += 1;
+        }
+        Ok(())
+    }
+
+    /// Parse one statement, which must run up to a colon or the end of the line, except for
+    /// `IF ... THEN`, which runs up to the statement it guards. Straight after the `THEN`, a
+    /// line number on its own is an implied `GOTO`.
+    fn parse_statement(&mut self, after_if: bool) -> ParseResult<Spanned<Statement>> {
+        let start = self.position;
+        let statement = if after_if && matches!(self.peek(), Some(Token::Number(_))) {
+            Statement::Goto(self.line_number()?)
         } else {
-            NodeType::Number
+            self.statement()?
+        };
+        if !matches!(statement, Statement::If(_)) && !self.at_statement_end() {
+            return Err(self.error("end of statement"));
+        }
+        Ok(Spanned {
+            value: statement,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Make an error statement holding the tokens from `start` to the end of the line
+    fn skip_line(
+        &mut self,
+        start: usize,
+        line: Option<u16>,
+        error: ParseError,
+    ) -> Spanned<Statement> {
+        self.position = start;
+        let mut tokens = Vec::new();
+        while let Some(token) = self.next_if(|token| *token != Token::Newline) {
+            tokens.push(token.clone());
+        }
+        Spanned {
+            value: Statement::Error {
+                error: ParseError { line, ..error },
+                tokens,
+            },
+            span: self.span_from(start),
+        }
+    }
+
+    fn statement(&mut self) -> ParseResult<Statement> {
+        let keyword = match self.peek() {
+            Some(Token::Identifier(..)) => return self.let_statement(),
+            Some(Token::Rem(text)) => {
+                self.advance();
+                return Ok(Statement::Rem(text.clone()));
+            }
+            Some(Token::Data(text)) => {
+                self.advance();
+                return Ok(Statement::Data(data_items(text)));
+            }
+            Some(Token::Keyword(keyword)) => *keyword,
+            _ => return Err(self.error("statement")),
         };
-        let node = ParseNode {
-            node_type,
-            value: token.to_owned(),
-            children: Vec::new(),
+        self.advance();
+        match keyword {
+            Keyword::Let => self.let_statement(),
+            Keyword::Print => self.print_statement(),
+            Keyword::End => Ok(Statement::End),
+            Keyword::Def => self.def_statement(),
+            Keyword::Dim => self.dim_statement(),
+            Keyword::For => self.for_statement(),
+            Keyword::Next => self.next_statement(),
+            Keyword::If => self.if_statement(),
+            Keyword::Goto => Ok(Statement::Goto(self.line_number()?)),
+            Keyword::Gosub => Ok(Statement::Gosub(self.line_number()?)),
+            Keyword::On => self.on_statement(),
+            Keyword::Return => Ok(Statement::Return),
+            Keyword::Pop => Ok(Statement::Pop),
+            Keyword::Read => self.read_statement(),
+            Keyword::Restore => Ok(Statement::Restore),
+            _ => {
+                // point the error at the keyword
+                self.position -= 1;
+                Err(self.error("statement"))
+            }
+        }
+    }
+
+    /// `LET` has been taken, if it was there
+    fn let_statement(&mut self) -> ParseResult<Statement> {
+        let variable = self.variable()?;
+        self.expect(&Token::Operator(Operator::Equal))?;
+        let value = self.expression()?;
+        Ok(Statement::Let { variable, value })
+    }
+
+    /// `PRINT` has been taken; items need no separator between them, so `PRINT "A"B` prints
+    /// the two values next to each other
+    fn print_statement(&mut self) -> ParseResult<Statement> {
+        let mut items = Vec::new();
+        while !self.at_statement_end() {
+            let item = if self.eat(&Token::Semicolon) {
+                PrintItem::Semicolon
+            } else if self.eat(&Token::Comma) {
+                PrintItem::Comma
+            } else if self.eat(&Token::Keyword(Keyword::Tab)) {
+                PrintItem::Tab(self.print_function_argument()?)
+            } else if self.eat(&Token::Keyword(Keyword::Spc)) {
+                PrintItem::Spc(self.print_function_argument()?)
+            } else {
+                PrintItem::Expr(self.expression()?)
+            };
+            items.push(item);
+        }
+        Ok(Statement::Print(items))
+    }
+
+    /// `TAB(` or `SPC(` has been taken, with the `(` that is part of the keyword
+    fn print_function_argument(&mut self) -> ParseResult<Spanned<Expr>> {
+        self.expect(&Token::LeftParen)?;
+        let argument = self.expression()?;
+        self.expect(&Token::RightParen)?;
+        Ok(argument)
+    }
+
+    /// `DEF` has been taken
+    fn def_statement(&mut self) -> ParseResult<Statement> {
+        self.expect(&Token::Keyword(Keyword::Fn))?;
+        let name = self.function_variable()?;
+        self.expect(&Token::LeftParen)?;
+        let parameter = self.function_variable()?;
+        self.expect(&Token::RightParen)?;
+        self.expect(&Token::Operator(Operator::Equal))?;
+        let body = self.expression()?;
+        Ok(Statement::Def {
+            name,
+            parameter,
+            body,
+        })
+    }
+
+    /// `DIM` has been taken
+    fn dim_statement(&mut self) -> ParseResult<Statement> {
+        let mut arrays = vec![self.array()?];
+        while self.eat(&Token::Comma) {
+            arrays.push(self.array()?);
+        }
+        Ok(Statement::Dim(arrays))
+    }
+
+    /// Parse an array with its subscripts
+    fn array(&mut self) -> ParseResult<Variable> {
+        let array = self.variable()?;
+        if array.subscripts.is_empty() {
+            return Err(self.error("("));
+        }
+        Ok(array)
+    }
+
+    /// `FOR` has been taken
+    fn for_statement(&mut self) -> ParseResult<Statement> {
+        let variable = self.loop_variable()?;
+        self.expect(&Token::Operator(Operator::Equal))?;
+        let start = self.expression()?;
+        self.expect(&Token::Keyword(Keyword::To))?;
+        let end = self.expression()?;
+        let step = if self.eat(&Token::Keyword(Keyword::Step)) {
+            Some(self.expression()?)
+        } else {
+            None
         };
-        return Some(node);
+        Ok(Statement::For {
+            variable,
+            start,
+            end,
+            step,
+        })
     }
-    None
-}
 
-pub fn parse_identifier(token: &str) -> Option<ParseNode> {
-    if token.chars().any(char::is_alphabetic) {
-        let node_type = if KEYWORDS.contains(&token) {
-            NodeType::StatementName
+    /// `READ` has been taken
+    fn read_statement(&mut self) -> ParseResult<Statement> {
+        let mut variables = vec![self.variable()?];
+        while self.eat(&Token::Comma) {
+            variables.push(self.variable()?);
+        }
+        Ok(Statement::Read(variables))
+    }
+
+    /// `NEXT` has been taken
+    fn next_statement(&mut self) -> ParseResult<Statement> {
+        let mut variables = Vec::new();
+        if !self.at_statement_end() {
+            variables.push(self.loop_variable()?);
+            while self.eat(&Token::Comma) {
+                variables.push(self.loop_variable()?);
+            }
+        }
+        Ok(Statement::Next(variables))
+    }
+
+    /// Parse the name or the parameter of a function defined with `DEF FN`, which must be a
+    /// simple floating point variable
+    pub fn function_variable(&mut self) -> ParseResult<Variable> {
+        match self.peek() {
+            Some(Token::Identifier(_, IdentifierKind::Float)) => self.variable_name(),
+            _ => Err(self.error("function name")),
+        }
+    }
+
+    /// Parse the variable of a FOR loop, which must be a simple floating point variable
+    fn loop_variable(&mut self) -> ParseResult<Variable> {
+        match self.peek() {
+            Some(Token::Identifier(_, IdentifierKind::Float)) => self.variable_name(),
+            _ => Err(self.error("loop variable")),
+        }
+    }
+
+    /// `IF` has been taken; the `GOTO` of `IF ... GOTO` is left to be parsed as the statement
+    /// the condition guards
+    fn if_statement(&mut self) -> ParseResult<Statement> {
+        let condition = self.expression()?;
+        if !matches!(self.peek(), Some(Token::Keyword(Keyword::Goto))) {
+            self.expect(&Token::Keyword(Keyword::Then))?;
+        }
+        Ok(Statement::If(condition))
+    }
+
+    /// `ON` has been taken
+    fn on_statement(&mut self) -> ParseResult<Statement> {
+        let index = self.expression()?;
+        let gosub = if self.eat(&Token::Keyword(Keyword::Gosub)) {
+            true
         } else {
-            NodeType::Identifier
+            self.expect(&Token::Keyword(Keyword::Goto))?;
+            false
         };
-        let node = ParseNode {
-            node_type,
-            value: token.to_owned(),
-            children: Vec::new(),
+        let mut targets = vec![self.line_number()?];
+        while self.eat(&Token::Comma) {
+            targets.push(self.line_number()?);
+        }
+        if gosub {
+            Ok(Statement::OnGosub { index, targets })
+        } else {
+            Ok(Statement::OnGoto { index, targets })
+        }
+    }
+
+    /// Parse a line number, such as the one that a statement such as `GOTO` refers to
+    fn line_number(&mut self) -> ParseResult<u16> {
+        let number = match self.peek() {
+            Some(Token::Number(number)) if number.chars().all(|ch| ch.is_ascii_digit()) => number
+                .parse()
+                .ok()
+                .filter(|number| *number <= MAX_LINE_NUMBER),
+            _ => None,
         };
-        return Some(node);
+        let number = number.ok_or_else(|| self.error("line number"))?;
+        self.advance();
+        Ok(number)
     }
-    None
-}
 
-pub fn parse_line_number(token: &str) -> Option<ParseNode> {
-    if token.chars().all(char::is_numeric) {
-        let node = ParseNode {
-            node_type: NodeType::LineNumber,
-            value: token.to_owned(),
-            children: Vec::new(),
+    /// Parse a variable name and the subscripts that may follow it
+    pub fn variable(&mut self) -> ParseResult<Variable> {
+        let start = self.position;
+        let mut variable = self.variable_name()?;
+        if self.eat(&Token::LeftParen) {
+            loop {
+                variable.subscripts.push(self.expression()?);
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+            self.expect(&Token::RightParen)?;
+            variable.span = self.span_from(start);
+        }
+        Ok(variable)
+    }
+
+    /// Parse a variable name without subscripts
+    fn variable_name(&mut self) -> ParseResult<Variable> {
+        let Some(Token::Identifier(name, kind)) = self.peek() else {
+            return Err(self.error("variable"));
         };
-        return Some(node);
+        let span = self.span_at(self.position);
+        self.advance();
+        Ok(Variable {
+            name: name.clone(),
+            kind: *kind,
+            subscripts: Vec::new(),
+            span,
+        })
+    }
+}
+
+/// Split the text of a DATA statement into its items at the commas outside quotes
+fn data_items(text: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut quoted = false;
+    for ch in text.chars() {
+        match ch {
+            ',' if !quoted => items.push(String::new()),
+            _ => {
+                quoted ^= ch == '"';
+                // Safety: there is always at least one item
+                #[allow(clippy::unwrap_used)]
+                items.last_mut().unwrap().push(ch);
+            }
+        }
     }
-    None
+    items
 }
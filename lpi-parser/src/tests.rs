@@ -1,450 +1,751 @@
 use super::*;
+use ast::{BinaryOp, Expr, Function, Line, PrintItem, Statement, UnaryOp, Variable};
+use lpi_lexer::{span::Span, token::IdentifierKind};
+
+/// Parse the source, blanking out every span so trees can be compared by structure alone
+fn parse_without_spans(source: &str) -> Program {
+    let mut program = parse_source(source);
+    for line in &mut program.lines {
+        line.span = Span::default();
+        for statement in &mut line.statements {
+            statement.span = Span::default();
+            statement.value.clear_spans();
+        }
+    }
+    program
+}
+
+/// Blank out the spans of a syntax tree node and of the nodes inside it
+trait ClearSpans {
+    fn clear_spans(&mut self);
+}
+
+impl ClearSpans for Statement {
+    fn clear_spans(&mut self) {
+        match self {
+            Self::Let { variable, value } => {
+                variable.clear_spans();
+                value.clear_spans();
+            }
+            Self::Print(items) => {
+                for item in items {
+                    match item {
+                        PrintItem::Expr(expr) | PrintItem::Tab(expr) | PrintItem::Spc(expr) => {
+                            expr.clear_spans();
+                        }
+                        PrintItem::Semicolon | PrintItem::Comma => {}
+                    }
+                }
+            }
+            Self::Def {
+                name,
+                parameter,
+                body,
+            } => {
+                name.clear_spans();
+                parameter.clear_spans();
+                body.clear_spans();
+            }
+            Self::Dim(variables) | Self::Next(variables) | Self::Read(variables) => {
+                variables.iter_mut().for_each(ClearSpans::clear_spans);
+            }
+            Self::For {
+                variable,
+                start,
+                end,
+                step,
+            } => {
+                variable.clear_spans();
+                start.clear_spans();
+                end.clear_spans();
+                step.iter_mut().for_each(ClearSpans::clear_spans);
+            }
+            Self::If(expr)
+            | Self::OnGoto { index: expr, .. }
+            | Self::OnGosub { index: expr, .. } => {
+                expr.clear_spans();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ClearSpans for Variable {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+        self.subscripts.iter_mut().for_each(ClearSpans::clear_spans);
+    }
+}
+
+impl ClearSpans for Spanned<Expr> {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+        match &mut self.value {
+            Expr::Number(_) | Expr::String(_) => {}
+            Expr::Variable(variable) => variable.clear_spans(),
+            Expr::Unary(_, operand) => operand.clear_spans(),
+            Expr::Binary(_, left, right) => {
+                left.clear_spans();
+                right.clear_spans();
+            }
+            Expr::Function(_, arguments) => arguments.iter_mut().for_each(ClearSpans::clear_spans),
+            Expr::UserFunction(name, argument) => {
+                name.clear_spans();
+                argument.clear_spans();
+            }
+        }
+    }
+}
+
+/// Parse source that holds no syntax errors
+fn parse_source(source: &str) -> Program {
+    #[allow(clippy::expect_used)]
+    parse(&lpi_lexer::lex(source)).expect("Error parsing program")
+}
+
+/// Parse the source and get the statements of its first line
+fn statements(source: &str) -> Vec<Statement> {
+    parse_without_spans(source).lines[0]
+        .statements
+        .iter()
+        .map(|statement| statement.value.clone())
+        .collect()
+}
+
+/// Parse a single PRINT statement and get the expression it prints
+fn printed_expression(source: &str) -> Spanned<Expr> {
+    match statements(source).as_slice() {
+        [Statement::Print(items)] => match items.as_slice() {
+            [PrintItem::Expr(expr)] => expr.clone(),
+            _ => panic!("{source} does not print a single expression"),
+        },
+        _ => panic!("{source} is not a single PRINT statement"),
+    }
+}
+
+fn statement(value: Statement) -> Spanned<Statement> {
+    Spanned {
+        value,
+        span: Span::default(),
+    }
+}
+
+fn expr(value: Expr) -> Spanned<Expr> {
+    Spanned {
+        value,
+        span: Span::default(),
+    }
+}
+
+fn number(value: f64) -> Spanned<Expr> {
+    expr(Expr::Number(value))
+}
+
+fn scalar(name: &str) -> Variable {
+    Variable {
+        name: name.to_owned(),
+        kind: IdentifierKind::Float,
+        subscripts: Vec::new(),
+        span: Span::default(),
+    }
+}
+
+fn variable(name: &str) -> Spanned<Expr> {
+    expr(Expr::Variable(scalar(name)))
+}
+
+fn binary(operator: BinaryOp, left: Spanned<Expr>, right: Spanned<Expr>) -> Spanned<Expr> {
+    expr(Expr::Binary(operator, Box::new(left), Box::new(right)))
+}
 
 #[test]
 fn test_parse() {
-    let tokens = vec![
-        "10".to_owned(),
-        "PRINT".to_owned(),
-        "\"HELLO, WORLD!\"".to_owned(),
-    ];
-    let parse = parse(&tokens);
-    let expected = ParseNode {
-        node_type: NodeType::Line,
-        value: String::new(),
-        children: vec![
-            ParseNode {
-                node_type: NodeType::LineNumber,
-                value: "10".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::StatementName,
-                value: "PRINT".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::String,
-                value: "\"HELLO, WORLD!\"".to_owned(),
-                children: Vec::new(),
-            },
-        ],
+    let parse = parse_without_spans("10 PRINT \"HELLO, WORLD!\"");
+    let expected = Line {
+        number: Some(10),
+        statements: vec![statement(Statement::Print(vec![PrintItem::Expr(expr(
+            Expr::String("HELLO, WORLD!".to_owned()),
+        ))]))],
+        span: Span::default(),
     };
 
-    assert_eq!(parse.children[0], expected);
+    assert_eq!(parse.lines[0], expected);
 }
 
 #[test]
 fn test_float_parse() {
-    let tokens = vec!["10".to_owned(), "PRINT".to_owned(), "3.14".to_owned()];
-    let parse = parse(&tokens);
-    let expected = ParseNode {
-        node_type: NodeType::Line,
-        value: String::new(),
-        children: vec![
-            ParseNode {
-                node_type: NodeType::LineNumber,
-                value: "10".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::StatementName,
-                value: "PRINT".to_owned(),
-                children: Vec::new(),
+    assert_eq!(printed_expression("10 PRINT 2.5"), number(2.5));
+}
+
+#[test]
+fn test_expression_parse() {
+    let expected = binary(
+        BinaryOp::Add,
+        binary(BinaryOp::Multiply, number(3.0), number(4.0)),
+        number(5.0),
+    );
+    assert_eq!(printed_expression("10 PRINT 3 * 4 + 5"), expected);
+}
+
+#[test]
+fn test_operator_precedence() {
+    let expected = binary(
+        BinaryOp::Add,
+        number(3.0),
+        binary(BinaryOp::Multiply, number(4.0), number(5.0)),
+    );
+    assert_eq!(printed_expression("10 PRINT 3 + 4 * 5"), expected);
+}
+
+#[test]
+fn test_parens() {
+    // the parentheses group the sum without leaving a node of their own
+    let expected = binary(
+        BinaryOp::Multiply,
+        binary(BinaryOp::Add, number(3.0), number(4.0)),
+        number(5.0),
+    );
+    assert_eq!(printed_expression("10 PRINT (3 + 4) * 5"), expected);
+}
+
+#[test]
+fn test_nesting_limit() {
+    let nested = |depth: usize| format!("10 PRINT {}1{}", "(".repeat(depth), ")".repeat(depth));
+    assert_eq!(printed_expression(&nested(36)), number(1.0));
+    // deeper nesting is a syntax error rather than a stack overflow
+    for source in [nested(37), format!("10 PRINT {}1", "-".repeat(100))] {
+        let Err(errors) = parse(&lpi_lexer::lex(&source)) else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(errors[0].expected, "fewer nested expressions");
+    }
+}
+
+#[test]
+fn test_line_length_limit() {
+    let chain = |terms: usize| format!("10 PRINT 1{}", "+1".repeat(terms - 1));
+    // 10 PRINT 1 takes 10 characters, and each further term 2
+    assert!(parse(&lpi_lexer::lex(&chain(115))).is_ok());
+    // a longer line, which could hold an operator chain too long to evaluate, is an error
+    for source in [
+        chain(116),
+        chain(3000),
+        format!("10 PRINT {}1", "(".repeat(3000)),
+    ] {
+        let Err(errors) = parse(&lpi_lexer::lex(&source)) else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(errors[0].expected, "line of at most 239 characters");
+        assert_eq!(errors[0].line, Some(10));
+    }
+}
+
+#[test]
+fn test_let_assignment() {
+    let expected = Statement::Let {
+        variable: scalar("A"),
+        value: binary(BinaryOp::Add, number(3.0), number(4.0)),
+    };
+    assert_eq!(statements("10 A = 3 + 4"), vec![expected.clone()]);
+    assert_eq!(statements("10 LET A = 3 + 4"), vec![expected]);
+}
+
+#[test]
+fn test_multiple_lines() {
+    let parse = parse_without_spans("10 PRINT 3 + 4\n20 PRINT 5 * 6");
+    let expected = Program {
+        lines: vec![
+            Line {
+                number: Some(10),
+                statements: vec![statement(Statement::Print(vec![PrintItem::Expr(binary(
+                    BinaryOp::Add,
+                    number(3.0),
+                    number(4.0),
+                ))]))],
+                span: Span::default(),
             },
-            ParseNode {
-                node_type: NodeType::Float,
-                value: "3.14".to_owned(),
-                children: Vec::new(),
+            Line {
+                number: Some(20),
+                statements: vec![statement(Statement::Print(vec![PrintItem::Expr(binary(
+                    BinaryOp::Multiply,
+                    number(5.0),
+                    number(6.0),
+                ))]))],
+                span: Span::default(),
             },
         ],
     };
 
-    assert_eq!(parse.children[0], expected);
+    assert_eq!(parse, expected);
 }
 
 #[test]
-fn test_expression_parse() {
-    let tokens = vec![
-        "10".to_owned(),
-        "PRINT".to_owned(),
-        "3".to_owned(),
-        "*".to_owned(),
-        "4".to_owned(),
-        "+".to_owned(),
-        "5".to_owned(),
-    ];
-    let parse = parse(&tokens);
-    let expected = ParseNode {
-        node_type: NodeType::Line,
-        value: String::new(),
-        children: vec![
-            ParseNode {
-                node_type: NodeType::LineNumber,
-                value: "10".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::StatementName,
-                value: "PRINT".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::Expression,
-                value: String::new(),
-                children: vec![
-                    ParseNode {
-                        node_type: NodeType::Expression,
-                        value: String::new(),
-                        children: vec![
-                            ParseNode {
-                                node_type: NodeType::Number,
-                                value: "3".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Symbol,
-                                value: "*".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Number,
-                                value: "4".to_owned(),
-                                children: Vec::new(),
-                            },
-                        ],
-                    },
-                    ParseNode {
-                        node_type: NodeType::Symbol,
-                        value: "+".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::Number,
-                        value: "5".to_owned(),
-                        children: Vec::new(),
-                    },
-                ],
-            },
-        ],
+fn test_spans() {
+    let parse = parse_source("10 PRINT 1\n20 A = 3 * 4");
+    let span = |start, end, line, column| Span {
+        start,
+        end,
+        line,
+        column,
     };
+    assert_eq!(parse.lines[0].span, span(0, 10, 1, 1));
+    let second_line = &parse.lines[1];
+    assert_eq!(second_line.span, span(11, 23, 2, 1));
+    assert_eq!(second_line.statements[0].span, span(14, 23, 2, 4));
 
-    assert_eq!(parse.children[0], expected);
+    // expressions and variables keep their spans in the tree, parentheses included
+    let parse = parse_source("20 A = 3 * 4\n30 B(I+1)=(2)");
+    let Statement::Let { variable, value } = &parse.lines[0].statements[0].value else {
+        panic!("expected a LET statement");
+    };
+    assert_eq!(variable.span, span(3, 4, 1, 4));
+    assert_eq!(value.span, span(7, 12, 1, 8));
+    let Expr::Binary(_, left, right) = &value.value else {
+        panic!("expected a binary operation");
+    };
+    assert_eq!(
+        (left.span, right.span),
+        (span(7, 8, 1, 8), span(11, 12, 1, 12))
+    );
+    let Statement::Let { variable, value } = &parse.lines[1].statements[0].value else {
+        panic!("expected a LET statement");
+    };
+    assert_eq!(variable.span, span(16, 22, 2, 4));
+    assert_eq!(variable.subscripts[0].span, span(18, 21, 2, 6));
+    assert_eq!(value.span, span(23, 26, 2, 11));
 }
 
 #[test]
-fn test_operator_precedence() {
-    let tokens = vec![
-        "10".to_owned(),
-        "PRINT".to_owned(),
-        "3".to_owned(),
-        "+".to_owned(),
-        "4".to_owned(),
-        "*".to_owned(),
-        "5".to_owned(),
+fn test_multiple_statements() {
+    let parse = statements("10 A = 1: B = 2 :: PRINT A : END");
+    assert!(matches!(
+        parse.as_slice(),
+        [
+            Statement::Let { .. },
+            Statement::Let { .. },
+            Statement::Print(_),
+            Statement::End
+        ]
+    ));
+}
+
+#[test]
+fn test_left_associativity() {
+    // each operator groups to the left, including `^`, and `^` binds tighter than `*`
+    let cases = [
+        ("10 PRINT 8 - 4 - 2", "((8 - 4) - 2)"),
+        ("10 PRINT 8 / 4 / 2", "((8 / 4) / 2)"),
+        ("10 PRINT 2 ^ 3 ^ 2", "((2 ^ 3) ^ 2)"),
+        ("10 PRINT 2 * 3 ^ 2", "(2 * (3 ^ 2))"),
+        ("10 PRINT (1 + 2) * 3", "((1 + 2) * 3)"),
     ];
-    let parse = parse(&tokens);
-    let expected = ParseNode {
-        node_type: NodeType::Line,
-        value: String::new(),
-        children: vec![
-            ParseNode {
-                node_type: NodeType::LineNumber,
-                value: "10".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::StatementName,
-                value: "PRINT".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::Expression,
-                value: String::new(),
-                children: vec![
-                    ParseNode {
-                        node_type: NodeType::Number,
-                        value: "3".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::Symbol,
-                        value: "+".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::Expression,
-                        value: String::new(),
-                        children: vec![
-                            ParseNode {
-                                node_type: NodeType::Number,
-                                value: "4".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Symbol,
-                                value: "*".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Number,
-                                value: "5".to_owned(),
-                                children: Vec::new(),
-                            },
-                        ],
-                    },
-                ],
-            },
-        ],
-    };
+    for (source, expected) in cases {
+        assert_eq!(bracketed(&printed_expression(source)), expected, "{source}");
+    }
+}
 
-    assert_eq!(parse.children[0], expected);
+#[test]
+fn test_relational_and_logical_precedence() {
+    let cases = [
+        ("10 PRINT A + 1 < B * 2", "((A + 1) < (B * 2))"),
+        (
+            "10 PRINT A = 1 AND B = 2 OR C",
+            "(((A = 1) AND (B = 2)) OR C)",
+        ),
+        ("10 PRINT A OR B AND C", "(A OR (B AND C))"),
+        ("10 PRINT A =< B", "(A <= B)"),
+        ("10 PRINT A => B", "(A >= B)"),
+        ("10 PRINT A >< B", "(A <> B)"),
+        ("10 PRINT A < = B", "(A <= B)"),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(bracketed(&printed_expression(source)), expected, "{source}");
+    }
 }
 
 #[test]
-fn test_parens() {
-    let tokens = vec![
-        "10".to_owned(),
-        "PRINT".to_owned(),
-        "(".to_owned(),
-        "3".to_owned(),
-        "+".to_owned(),
-        "4".to_owned(),
-        ")".to_owned(),
-        "*".to_owned(),
-        "5".to_owned(),
+fn test_let_is_not_a_comparison() {
+    let parse = statements("10 A(I + 1) = B = C");
+    let [Statement::Let { variable, value }] = parse.as_slice() else {
+        panic!("expected a single LET statement");
+    };
+    assert_eq!(variable.name, "A");
+    let [subscript] = variable.subscripts.as_slice() else {
+        panic!("expected a single subscript");
+    };
+    assert_eq!(bracketed(subscript), "(I + 1)");
+    assert_eq!(bracketed(value), "(B = C)");
+}
+
+#[test]
+fn test_unary_operators() {
+    // unary operators bind tighter than `*` but looser than `^`
+    let cases = [
+        ("10 PRINT -5", "(- 5)"),
+        ("10 PRINT 2 * -B", "(2 * (- B))"),
+        ("10 PRINT -2 ^ 2", "(- (2 ^ 2))"),
+        ("10 PRINT 2 ^ -1", "(2 ^ (- 1))"),
+        ("10 PRINT - - 3 + + 4", "((- (- 3)) + (+ 4))"),
+        ("10 PRINT NOT A = B", "((NOT A) = B)"),
+        ("10 PRINT NOT (A = B) AND C", "((NOT (A = B)) AND C)"),
     ];
-    let parse = parse(&tokens);
-    let expected = ParseNode {
-        node_type: NodeType::Line,
-        value: String::new(),
-        children: vec![
-            ParseNode {
-                node_type: NodeType::LineNumber,
-                value: "10".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::StatementName,
-                value: "PRINT".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::Expression,
-                value: String::new(),
-                children: vec![
-                    ParseNode {
-                        node_type: NodeType::Expression,
-                        value: String::new(),
-                        children: vec![
-                            ParseNode {
-                                node_type: NodeType::Symbol,
-                                value: "(".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Expression,
-                                value: String::new(),
-                                children: vec![
-                                    ParseNode {
-                                        node_type: NodeType::Number,
-                                        value: "3".to_owned(),
-                                        children: Vec::new(),
-                                    },
-                                    ParseNode {
-                                        node_type: NodeType::Symbol,
-                                        value: "+".to_owned(),
-                                        children: Vec::new(),
-                                    },
-                                    ParseNode {
-                                        node_type: NodeType::Number,
-                                        value: "4".to_owned(),
-                                        children: Vec::new(),
-                                    },
-                                ],
-                            },
-                            ParseNode {
-                                node_type: NodeType::Symbol,
-                                value: ")".to_owned(),
-                                children: Vec::new(),
-                            },
-                        ],
-                    },
-                    ParseNode {
-                        node_type: NodeType::Symbol,
-                        value: "*".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::Number,
-                        value: "5".to_owned(),
-                        children: Vec::new(),
-                    },
-                ],
-            },
-        ],
+    for (source, expected) in cases {
+        assert_eq!(bracketed(&printed_expression(source)), expected, "{source}");
+    }
+}
+
+#[test]
+fn test_print_items() {
+    let expected = vec![Statement::Print(vec![
+        PrintItem::Tab(number(5.0)),
+        PrintItem::Expr(expr(Expr::String("A".to_owned()))),
+        PrintItem::Expr(variable("B")),
+        PrintItem::Comma,
+        PrintItem::Spc(binary(BinaryOp::Add, number(1.0), number(2.0))),
+        PrintItem::Semicolon,
+    ])];
+    assert_eq!(statements("10 ?TAB(5)\"A\"B,SPC(1+2);"), expected);
+    let Err(errors) = parse(&lpi_lexer::lex("10 PRINT TAB(5")) else {
+        panic!("expected a syntax error");
     };
+    assert_eq!(errors[0].expected, ")");
+}
 
-    assert_eq!(parse.children[0], expected);
+#[test]
+fn test_functions() {
+    let call = |function, argument| expr(Expr::Function(function, vec![argument]));
+    let expected = binary(
+        BinaryOp::Add,
+        call(
+            Function::Int,
+            binary(BinaryOp::Multiply, variable("X"), number(2.0)),
+        ),
+        call(Function::Abs, call(Function::Sin, number(1.0))),
+    );
+    assert_eq!(
+        printed_expression("10 PRINT INT(X*2)+ABS(SIN(1))"),
+        expected
+    );
+    let string = expr(Expr::Variable(Variable {
+        kind: IdentifierKind::String,
+        ..scalar("A")
+    }));
+    let expected = expr(Expr::Function(
+        Function::Mid,
+        vec![string.clone(), number(2.0)],
+    ));
+    assert_eq!(printed_expression("10 PRINT MID$(A$,2)"), expected);
+    let expected = expr(Expr::Function(
+        Function::Mid,
+        vec![string, number(2.0), number(1.0)],
+    ));
+    assert_eq!(printed_expression("10 PRINT MID$(A$,2,1)"), expected);
+    for (source, expected) in [
+        ("10 PRINT SQR 2", "("),
+        ("10 PRINT LOG(1,2)", ")"),
+        ("10 PRINT LEFT$(A$)", ","),
+        ("10 PRINT MID$(A$,1,2,3)", ")"),
+    ] {
+        let Err(errors) = parse(&lpi_lexer::lex(source)) else {
+            panic!("expected a syntax error in {source}");
+        };
+        assert_eq!(errors[0].expected, expected);
+    }
 }
 
 #[test]
-fn test_let_assignment() {
-    let tokens = vec![
-        "10".to_owned(),
-        "A".to_owned(),
-        "=".to_owned(),
-        "3".to_owned(),
-        "+".to_owned(),
-        "4".to_owned(),
+fn test_def_fn() {
+    let expected = vec![
+        Statement::Def {
+            name: scalar("F"),
+            parameter: scalar("X"),
+            body: binary(
+                BinaryOp::Add,
+                binary(BinaryOp::Multiply, variable("X"), variable("X")),
+                number(1.0),
+            ),
+        },
+        Statement::Print(vec![PrintItem::Expr(expr(Expr::UserFunction(
+            scalar("F"),
+            Box::new(number(3.0)),
+        )))]),
     ];
-    let parse = parse(&tokens);
-    let expected = ParseNode {
-        node_type: NodeType::Line,
-        value: String::new(),
-        children: vec![
-            ParseNode {
-                node_type: NodeType::LineNumber,
-                value: "10".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::StatementName,
-                value: "LET".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::Identifier,
-                value: "A".to_owned(),
-                children: Vec::new(),
-            },
-            ParseNode {
-                node_type: NodeType::Symbol,
-                value: "=".to_owned(),
-                children: Vec::new(),
+    assert_eq!(statements("10 DEF FN F(X) = X*X+1:PRINT FN F(3)"), expected);
+    for (source, expected) in [
+        ("10 DEF F(X) = X", "FN"),
+        ("10 DEF FN F$(X) = X", "function name"),
+        ("10 DEF FN F(X%) = X", "function name"),
+        ("10 DEF FN F(X)", "="),
+        ("10 PRINT FN F", "("),
+    ] {
+        let Err(errors) = parse(&lpi_lexer::lex(source)) else {
+            panic!("expected a syntax error in {source}");
+        };
+        assert_eq!(errors[0].expected, expected);
+    }
+}
+
+#[test]
+fn test_data_and_read() {
+    let items = [" 1", " \"A,B\"", "", " C D ", "-2.5E3"];
+    let expected = vec![
+        Statement::Data(items.iter().map(|&item| item.to_owned()).collect()),
+        Statement::Read(vec![
+            scalar("A"),
+            Variable {
+                kind: IdentifierKind::String,
+                subscripts: vec![number(1.0)],
+                ..scalar("B")
             },
-            ParseNode {
-                node_type: NodeType::Expression,
-                value: String::new(),
-                children: vec![
-                    ParseNode {
-                        node_type: NodeType::Number,
-                        value: "3".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::Symbol,
-                        value: "+".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::Number,
-                        value: "4".to_owned(),
-                        children: Vec::new(),
-                    },
-                ],
+        ]),
+        Statement::Restore,
+    ];
+    let source = "10 DATA 1, \"A,B\",, C D ,-2.5E3:READ A,B$(1):RESTORE";
+    assert_eq!(statements(source), expected);
+    assert_eq!(
+        statements("10 DATA"),
+        vec![Statement::Data(vec![String::new()])]
+    );
+    let Err(errors) = parse(&lpi_lexer::lex("10 READ A,")) else {
+        panic!("expected a syntax error");
+    };
+    assert_eq!(errors[0].expected, "variable");
+}
+
+#[test]
+fn test_dim() {
+    let array = |name: &str, kind, subscripts: &[f64]| Variable {
+        kind,
+        subscripts: subscripts.iter().copied().map(number).collect(),
+        ..scalar(name)
+    };
+    let expected = vec![Statement::Dim(vec![
+        array("A", IdentifierKind::Float, &[10.0, 5.0, 2.0]),
+        array("B", IdentifierKind::String, &[20.0]),
+    ])];
+    assert_eq!(statements("10 DIM A(10,5,2),B$(20)"), expected);
+    let Err(errors) = parse(&lpi_lexer::lex("10 DIM A(1),B")) else {
+        panic!("expected a syntax error");
+    };
+    assert_eq!(errors[0].expected, "(");
+}
+
+#[test]
+fn test_for_next() {
+    assert_eq!(
+        statements("10 FOR I = 1 TO N STEP -2: NEXT: NEXT I, J"),
+        vec![
+            Statement::For {
+                variable: scalar("I"),
+                start: number(1.0),
+                end: variable("N"),
+                step: Some(expr(Expr::Unary(UnaryOp::Negate, Box::new(number(2.0))))),
             },
-        ],
+            Statement::Next(Vec::new()),
+            Statement::Next(vec![scalar("I"), scalar("J")]),
+        ]
+    );
+    assert!(matches!(
+        statements("10 FOR I = 1 TO 10").as_slice(),
+        [Statement::For { step: None, .. }]
+    ));
+
+    // the loop variable must be a simple floating point variable
+    let Err(errors) = parse(&lpi_lexer::lex(
+        "10 FOR I% = 1 TO 2\n20 FOR A(1) = 1 TO 2\n30 NEXT I$",
+    )) else {
+        panic!("expected syntax errors");
     };
+    let expected: Vec<&str> = errors.iter().map(|error| error.expected.as_str()).collect();
+    assert_eq!(expected, vec!["loop variable", "=", "loop variable"]);
+}
+
+#[test]
+fn test_if() {
+    let condition = || binary(BinaryOp::Less, variable("A"), number(2.0));
+    assert_eq!(
+        statements("10 IF A < 2 THEN PRINT: END"),
+        vec![
+            Statement::If(condition()),
+            Statement::Print(Vec::new()),
+            Statement::End
+        ]
+    );
+    // THEN followed by a line number, and IF ... GOTO, both go to the line
+    let expected = vec![Statement::If(condition()), Statement::Goto(100)];
+    assert_eq!(statements("10 IF A < 2 THEN 100"), expected);
+    assert_eq!(statements("10 IF A < 2 GOTO 100"), expected);
+    assert_eq!(statements("10 IF A < 2 THEN GOTO 100"), expected);
 
-    assert_eq!(parse.children[0], expected);
+    // a line number is only digits, so the `.5` is left over after line 1
+    let source = "10 IF A PRINT\n20 IF B THEN 1.5\n30 IF B THEN 64000";
+    let Err(errors) = parse(&lpi_lexer::lex(source)) else {
+        panic!("expected syntax errors");
+    };
+    let expected: Vec<&str> = errors.iter().map(|error| error.expected.as_str()).collect();
+    assert_eq!(expected, vec!["THEN", "end of statement", "line number"]);
 }
 
 #[test]
-fn test_multiple_lines() {
-    let tokens = vec![
-        "10".to_owned(),
-        "PRINT".to_owned(),
-        "3".to_owned(),
-        "+".to_owned(),
-        "4".to_owned(),
-        "\n".to_owned(),
-        "20".to_owned(),
-        "PRINT".to_owned(),
-        "5".to_owned(),
-        "*".to_owned(),
-        "6".to_owned(),
+fn test_goto() {
+    let expected = vec![
+        Statement::Goto(100),
+        Statement::OnGoto {
+            index: binary(BinaryOp::Add, variable("X"), number(1.0)),
+            targets: vec![10, 20, 30],
+        },
     ];
-    let parse = parse(&tokens);
-    let expected = ParseNode {
-        node_type: NodeType::Program,
-        value: String::new(),
-        children: vec![
-            ParseNode {
-                node_type: NodeType::Line,
-                value: String::new(),
-                children: vec![
-                    ParseNode {
-                        node_type: NodeType::LineNumber,
-                        value: "10".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::StatementName,
-                        value: "PRINT".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::Expression,
-                        value: String::new(),
-                        children: vec![
-                            ParseNode {
-                                node_type: NodeType::Number,
-                                value: "3".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Symbol,
-                                value: "+".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Number,
-                                value: "4".to_owned(),
-                                children: Vec::new(),
-                            },
-                        ],
-                    },
-                ],
-            },
-            ParseNode {
-                node_type: NodeType::Line,
-                value: String::new(),
-                children: vec![
-                    ParseNode {
-                        node_type: NodeType::LineNumber,
-                        value: "20".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::StatementName,
-                        value: "PRINT".to_owned(),
-                        children: Vec::new(),
-                    },
-                    ParseNode {
-                        node_type: NodeType::Expression,
-                        value: String::new(),
-                        children: vec![
-                            ParseNode {
-                                node_type: NodeType::Number,
-                                value: "5".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Symbol,
-                                value: "*".to_owned(),
-                                children: Vec::new(),
-                            },
-                            ParseNode {
-                                node_type: NodeType::Number,
-                                value: "6".to_owned(),
-                                children: Vec::new(),
-                            },
-                        ],
-                    },
-                ],
+    assert_eq!(statements("10 GOTO 100: ON X + 1 GOTO 10, 20,30"), expected);
+    assert_eq!(
+        statements("10 GOSUB 100: ON X GOSUB 10, 20: RETURN: POP"),
+        vec![
+            Statement::Gosub(100),
+            Statement::OnGosub {
+                index: variable("X"),
+                targets: vec![10, 20],
             },
-        ],
+            Statement::Return,
+            Statement::Pop,
+        ]
+    );
+
+    let Err(errors) = parse(&lpi_lexer::lex("10 GOTO X\n20 ON X GOTO 10,")) else {
+        panic!("expected syntax errors");
     };
+    let expected: Vec<&str> = errors.iter().map(|error| error.expected.as_str()).collect();
+    assert_eq!(expected, vec!["line number", "line number"]);
+}
 
-    assert_eq!(parse, expected);
+#[test]
+fn test_line_number_before_exponent_letter() {
+    for source in ["10 E1=5", "10E1=5"] {
+        let program = parse_without_spans(source);
+        assert_eq!(program.lines[0].number, Some(10));
+        assert_eq!(
+            program.lines[0].statements,
+            vec![statement(Statement::Let {
+                variable: scalar("E1"),
+                value: number(5.0),
+            })]
+        );
+    }
+}
+
+#[test]
+fn test_line_number_range() {
+    assert_eq!(
+        parse_source("63999 GOTO 63999").lines[0].number,
+        Some(63999)
+    );
+    // a number past 63999 is a syntax error rather than a line without a number
+    let source = "10 PRINT 1\n64000 PRINT 2\n70000 PRINT 3\n20 GOTO 65535";
+    let Err(errors) = parse(&lpi_lexer::lex(source)) else {
+        panic!("expected syntax errors");
+    };
+    let found: Vec<(Option<u16>, &str, String)> = errors
+        .iter()
+        .map(|error| (error.line, error.expected.as_str(), error.found.to_string()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (None, "line number", "64000".to_owned()),
+            (None, "line number", "70000".to_owned()),
+            (Some(20), "line number", "65535".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    // every bad line is reported, each with the first error on it
+    let source = "10 A =\n20 PRINT (1:END\n30 A = 1)\n40 PRINT 1\n50 HGR\n60 PRINT (";
+    let Err(errors) = parse(&lpi_lexer::lex(source)) else {
+        panic!("expected syntax errors");
+    };
+    let found: Vec<(Option<u16>, usize, &str, String)> = errors
+        .iter()
+        .map(|error| {
+            (
+                error.line,
+                error.column(),
+                error.expected.as_str(),
+                error.found.to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (Some(10), 7, "expression", "end of line".to_owned()),
+            (Some(20), 12, ")", ":".to_owned()),
+            (Some(30), 9, "end of statement", ")".to_owned()),
+            (Some(50), 4, "statement", "HGR".to_owned()),
+            (Some(60), 11, "expression", "end of line".to_owned()),
+        ]
+    );
+    assert_eq!(
+        errors[2].to_string(),
+        "in line 30 at line 3, column 9: expected end of statement, found )"
+    );
+}
+
+#[test]
+fn test_error_recovery() {
+    // the statements before the error are kept, and the lines after it are parsed as usual
+    let program = parse_with_recovery(&lpi_lexer::lex("10 PRINT 1: A = ): PRINT 2\n20 PRINT 3"));
+    let [first, second] = program.lines.as_slice() else {
+        panic!("expected two lines");
+    };
+    let [print, error] = first.statements.as_slice() else {
+        panic!("expected two statements");
+    };
+    assert!(matches!(print.value, Statement::Print(_)));
+    let Statement::Error { error, tokens } = &error.value else {
+        panic!("expected an error statement");
+    };
+    assert_eq!((error.line, error.column()), (Some(10), 17));
+    assert_eq!(tokens.len(), 6);
+    assert!(matches!(
+        second.statements.as_slice(),
+        [Spanned {
+            value: Statement::Print(_),
+            ..
+        }]
+    ));
+    // the bad line can still be listed
+    assert_eq!(program.to_string(), "10 PRINT 1:A=): PRINT 2\n20 PRINT 3\n");
+}
+
+#[test]
+fn test_display() {
+    let source =
+        "10 A(I,2) = -(B+1)*2^3:PRINT \"X\";A;:END\n20 REM HI\n30 ON A GOTO 10,20:GOTO 10\n40 ON A GOSUB 10:GOSUB 20:POP:RETURN\n50 FOR I = 1 TO 9 STEP 2:NEXT:NEXT I,J\n60 IF A=1 THEN PRINT 1:IF B THEN GOTO 10\n70 PRINT TAB(I+1)\"X\"SPC(2),\n80 DIM A(10,5),B$(N+1):END\n90 PRINT SGN(-X)*EXP(LOG(2)),ATN(1)\n100 A$ = LEFT$(B$,2)+MID$(STR$(LEN(B$)),1)+CHR$(ASC(\"A\")):A = VAL(A$)\n110 A = INT(RND(1)*6)+1\n120 DEF FN SQ(X) = X*X:PRINT FN SQ(FN SQ(2))\n130 DATA 1, \"A:B\",,X :READ A,B$(I):RESTORE\n";
+    assert_eq!(parse_source(source).to_string(), source);
+    let expression = printed_expression("10 PRINT 8 - (4 - 2) - NOT A");
+    assert_eq!(expression.to_string(), "8-(4-2)-NOT A");
+}
+
+/// Write an expression tree with every operation in parentheses
+fn bracketed(expr: &Spanned<Expr>) -> String {
+    match &expr.value {
+        Expr::Unary(operator, operand) => {
+            let operator = match operator {
+                UnaryOp::Negate => "-",
+                UnaryOp::Plus => "+",
+                UnaryOp::Not => "NOT",
+            };
+            format!("({operator} {})", bracketed(operand))
+        }
+        Expr::Binary(operator, left, right) => {
+            let operator = operator.to_string();
+            format!(
+                "({} {} {})",
+                bracketed(left),
+                operator.trim(),
+                bracketed(right)
+            )
+        }
+        _ => expr.to_string(),
+    }
 }
@@ -4,7 +4,14 @@ fn main() {
     let input = include_str!("../programs/hello_world.bas");
     let tokens = lpi_lexer::lex(input);
     println!("Lexer: {tokens:?}");
-    let parse = lpi_parser::parse(&tokens);
-    println!("Parser: {parse}");
-    let _unused = lpi_interpreter::interpret(&mut std::io::stdout(), &parse);
+    let parse = lpi_parser::parse_with_recovery(&tokens);
+    println!("Parser:\n{parse}");
+    let mut interpreter = lpi_interpreter::Interpreter::new(&parse);
+    interpreter.set_strict(true);
+    if let Err(error) = interpreter.interpret(&mut std::io::stdout()) {
+        println!("{error} (at {})", error.span);
+    }
+    for warning in interpreter.warnings() {
+        println!("Warning: {warning}");
+    }
 }